@@ -0,0 +1,217 @@
+use crate::minmax::{ranked_moves_unless, search_value};
+use crate::{BoardEssentials, Colour, Square, BOARD_SIZE};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+// Searches for the window that run in the background, so that it keeps responding:
+// - Analysis scores every legal move of a position, one ply deeper at a time up to a maximum
+//   depth, for the analysis overlay. The search of a position is given up once it is no longer
+//   wanted, which is checked between two moves, so a search left behind by a move only runs on for
+//   as long as the move it is searching takes.
+// - Evaluator evaluates the positions of a game one after the other, for the evaluation bar and
+//   graph, and remembers the values.
+// - AiMove runs the AI's search for its move (see Controller::ai_search), so the TUI keeps its
+//   clock and keys going while the AI thinks.
+
+// A position by its board and side to move.
+type Position = ([[Colour; BOARD_SIZE]; BOARD_SIZE], bool);
+
+// The legal moves of the position searched `depth` plies deep, best first for the side to move,
+// with their values from white's point of view (see minmax::ranked_moves).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scores {
+    pub depth: usize,
+    pub moves: Vec<(Square, isize)>,
+}
+
+pub struct Analysis {
+    position: Position,
+    receiver: Receiver<Scores>,
+    stop: Arc<AtomicBool>,
+    // the deepest scores received so far
+    latest: Option<Scores>,
+}
+
+impl Analysis {
+    pub fn start(board_essential: &BoardEssentials, max_depth: usize) -> Analysis {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let position = board_essential.clone();
+        let stopped = Arc::clone(&stop);
+        thread::spawn(move || {
+            for depth in 1..=max_depth {
+                let Some(moves) = ranked_moves_unless(&position, depth, || stopped.load(Ordering::Relaxed)) else {
+                    return;
+                };
+                // no legal moves, or nobody listening any more
+                if moves.is_empty() || sender.send(Scores { depth, moves }).is_err() {
+                    return;
+                }
+            }
+        });
+        Analysis {
+            position: (board_essential.board, board_essential.white_turn),
+            receiver,
+            stop,
+            latest: None,
+        }
+    }
+
+    // Whether this is the analysis of the position.
+    pub fn is_for(&self, board_essential: &BoardEssentials) -> bool {
+        self.position == (board_essential.board, board_essential.white_turn)
+    }
+
+    // Takes in the scores the search found since the last call.
+    pub fn poll(&mut self) {
+        if let Some(scores) = self.receiver.try_iter().last() {
+            self.latest = Some(scores);
+        }
+    }
+
+    pub fn scores(&self) -> Option<&Scores> {
+        self.latest.as_ref()
+    }
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+pub struct Evaluator {
+    depth: usize,
+    // the worker is started with the first position asked for
+    requests: Option<Sender<BoardEssentials>>,
+    receiver: Option<Receiver<(Position, isize)>>,
+    asked: HashSet<Position>,
+    values: HashMap<Position, isize>,
+}
+
+impl Evaluator {
+    pub fn new(depth: usize) -> Evaluator {
+        Evaluator { depth, requests: None, receiver: None, asked: HashSet::new(), values: HashMap::new() }
+    }
+
+    // Asks for the search value of the position, see minmax::search_value, unless it was asked for
+    // before.
+    pub fn request(&mut self, board_essential: &BoardEssentials) {
+        if !self.asked.insert((board_essential.board, board_essential.white_turn)) {
+            return;
+        }
+        if self.requests.is_none() {
+            let (requests, positions) = mpsc::channel::<BoardEssentials>();
+            let (sender, receiver) = mpsc::channel();
+            let depth = self.depth;
+            // ends when the evaluator is dropped, after the position it is searching
+            thread::spawn(move || {
+                for position in positions {
+                    let value = search_value(&position, depth);
+                    if sender.send(((position.board, position.white_turn), value)).is_err() {
+                        return;
+                    }
+                }
+            });
+            self.requests = Some(requests);
+            self.receiver = Some(receiver);
+        }
+        if let Some(requests) = &self.requests {
+            let _ = requests.send(board_essential.clone());
+        }
+    }
+
+    // Takes in the values found since, and tells whether there were any.
+    pub fn poll(&mut self) -> bool {
+        let known = self.values.len();
+        if let Some(receiver) = &self.receiver {
+            self.values.extend(receiver.try_iter());
+        }
+        self.values.len() > known
+    }
+
+    pub fn value(&self, board_essential: &BoardEssentials) -> Option<isize> {
+        self.values.get(&(board_essential.board, board_essential.white_turn)).copied()
+    }
+}
+
+pub struct AiMove {
+    position: Position,
+    receiver: Receiver<Square>,
+}
+
+impl AiMove {
+    // The search runs on until it is done, even if the move is no longer wanted.
+    pub fn start(board_essential: &BoardEssentials, search: impl FnOnce() -> Option<Square> + Send + 'static) -> AiMove {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            if let Some(square) = search() {
+                let _ = sender.send(square);
+            }
+        });
+        AiMove { position: (board_essential.board, board_essential.white_turn), receiver }
+    }
+
+    // Whether this is the search for a move in the position.
+    pub fn is_for(&self, board_essential: &BoardEssentials) -> bool {
+        self.position == (board_essential.board, board_essential.white_turn)
+    }
+
+    // The move, once it is found.
+    pub fn poll(&self) -> Option<Square> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_board_essentials;
+    use crate::minmax::ranked_moves;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn scores_deepen_up_to_the_maximum_depth() {
+        let position = init_board_essentials();
+        let mut analysis = Analysis::start(&position, 3);
+        assert!(analysis.is_for(&position));
+        let started = Instant::now();
+        while analysis.scores().is_none_or(|scores| scores.depth < 3) {
+            assert!(started.elapsed() < Duration::from_secs(30), "no depth 3 scores");
+            thread::sleep(Duration::from_millis(10));
+            analysis.poll();
+        }
+        let scores = analysis.scores().unwrap().clone();
+        assert_eq!(scores.moves.len(), 4);
+        // the same as a search straight to that depth
+        assert_eq!(scores.moves, ranked_moves(&position, 3));
+
+        let mut other = position.clone();
+        other.white_turn = !other.white_turn;
+        assert!(!analysis.is_for(&other));
+    }
+
+    #[test]
+    fn evaluator_remembers_the_values_asked_for() {
+        let start = init_board_essentials();
+        let mut after_c4 = start.clone();
+        crate::do_move_essentials(2, 3, &mut after_c4);
+        let mut evaluator = Evaluator::new(2);
+        assert_eq!(evaluator.value(&start), None);
+        for position in [&start, &after_c4, &start] {
+            evaluator.request(position);
+        }
+        let started = Instant::now();
+        while evaluator.value(&after_c4).is_none() {
+            assert!(started.elapsed() < Duration::from_secs(30), "no value for c4");
+            thread::sleep(Duration::from_millis(10));
+            evaluator.poll();
+        }
+        assert_eq!(evaluator.value(&start), Some(search_value(&start, 2)));
+        assert_eq!(evaluator.value(&after_c4), Some(search_value(&after_c4, 2)));
+        assert_eq!(evaluator.asked.len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::minmax::{evaluate_game, get_for_whoever_best_move, node_count, reset_node_count};
+use crate::settings::settings;
+use crate::{do_move_essentials, get_all_possible_moves, legal_moves, BoardEssentials};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// Performance benchmarks on a fixed set of positions, so optimizations can be quantified and
+// regressions noticed. `othello bench` searches every position to a fixed depth and prints the
+// total nodes and nodes per second; `--micro` also times the heavily used board functions.
+// Usage: othello bench [--micro]
+
+// Positions reached by pseudo-random play from the start, 20-28 and 40-48 plies in.
+const POSITIONS: [(&str, &str); 8] = [
+    ("midgame-1", "XXXO-----O-XO----OOOX----OOOOX--O-XOX-------OXO-------X--------- O"),
+    ("midgame-2", "------------X-------X-OO---XXXOO---XXXOOXXXXXXO------OO-------O- O"),
+    ("midgame-3", "------O---X--OX----XOXX--XXXXOX----XOXO---XXXO-O--O-XXO--------O O"),
+    ("midgame-4", "---OOOOOX-O-O-O--XOOOO----OOOO--XXXXXX---OXX-XX--X---X--X------- O"),
+    ("endgame-1", "--X-OO--X-XOOO--XXOOOOX-XXXOOX--OX-XXXXX-X-XXOXX--XOOXX----XXXXX O"),
+    ("endgame-2", "OXXOOO--OOOXXX-OOOOXX-O-OX-OOOOX-XXXOOXX--XXOOXX---XXXXX----XXXO O"),
+    ("endgame-3", "--OOXOO-OOO-XXXXOOXXX-XXOOXXXXOO--XXXXO---XXXXOX-OOOOOO---XXXOOO O"),
+    ("endgame-4", "XXO-OX--XOXOXOX-X-OXXXX-XXXOXXXOXXOOXXOOO-XOOOOOOOOOOX-XX-X-X-X- O"),
+];
+
+pub fn bench_positions() -> Vec<(&'static str, BoardEssentials)> {
+    POSITIONS
+        .iter()
+        .map(|(name, position)| (*name, position.parse().unwrap()))
+        .collect()
+}
+
+pub struct Timing {
+    pub mean: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+// Criterion-style measurement: warm up, then time a number of samples of equally many
+// iterations each and report the per-iteration time. Criterion itself would need a harness
+// under benches/, which can only call a library target; this crate is a single binary, so the
+// board functions are timed here and run from the same release build as the search benchmark.
+pub fn measure<F: FnMut()>(mut routine: F) -> Timing {
+    const WARM_UP: Duration = Duration::from_millis(200);
+    const SAMPLE_TIME: Duration = Duration::from_millis(40);
+    const SAMPLES: u32 = 20;
+
+    let start = Instant::now();
+    let mut warm_up_iterations = 0u32;
+    while start.elapsed() < WARM_UP {
+        routine();
+        warm_up_iterations += 1;
+    }
+    let per_iteration = start.elapsed() / warm_up_iterations;
+    let iterations = (SAMPLE_TIME.as_nanos() / per_iteration.as_nanos().max(1)).max(1) as u32;
+
+    let (mut total, mut min, mut max) = (Duration::ZERO, Duration::MAX, Duration::ZERO);
+    for _ in 0..SAMPLES {
+        let sample_start = Instant::now();
+        for _ in 0..iterations {
+            routine();
+        }
+        let sample = sample_start.elapsed() / iterations;
+        total += sample;
+        min = min.min(sample);
+        max = max.max(sample);
+    }
+    Timing { mean: total / SAMPLES, min, max }
+}
+
+fn print_timing(name: &str, timing: &Timing) {
+    println!(
+        "{:<40} time: [{:>10.3?} {:>10.3?} {:>10.3?}]",
+        name, timing.min, timing.mean, timing.max
+    );
+}
+
+pub fn run_micro_benchmarks(positions: &[(&str, BoardEssentials)]) {
+    for (name, position) in positions {
+        let timing = measure(|| {
+            black_box(get_all_possible_moves(black_box(position)));
+        });
+        print_timing(&format!("get_all_possible_moves/{}", name), &timing);
+
+        let moves = legal_moves(position);
+        let timing = measure(|| {
+            for sq in moves.iter() {
+                let mut clone = position.clone();
+                do_move_essentials(sq.x, sq.y, &mut clone);
+                black_box(clone);
+            }
+        });
+        let per_move = Timing {
+            mean: timing.mean / moves.len() as u32,
+            min: timing.min / moves.len() as u32,
+            max: timing.max / moves.len() as u32,
+        };
+        print_timing(&format!("do_move_essentials/{}", name), &per_move);
+
+        let timing = measure(|| {
+            black_box(evaluate_game(black_box(position)));
+        });
+        print_timing(&format!("evaluate_game/{}", name), &timing);
+    }
+}
+
+// Searches every position to `depth` and returns the total nodes and time spent.
+pub fn run_search_benchmark(positions: &[(&str, BoardEssentials)], depth: usize) -> (u64, Duration) {
+    let (mut total_nodes, mut total_time) = (0, Duration::ZERO);
+    for (name, position) in positions {
+        reset_node_count();
+        let start = Instant::now();
+        black_box(get_for_whoever_best_move(position, depth));
+        let elapsed = start.elapsed();
+        let nodes = node_count();
+        println!("search/{:<12} depth {:<2} nodes {:>10} time {:>10.3?}", name, depth, nodes, elapsed);
+        total_nodes += nodes;
+        total_time += elapsed;
+    }
+    (total_nodes, total_time)
+}
+
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let depth = settings().depth;
+    let mut micro = false;
+    for arg in args {
+        match arg.as_str() {
+            "--micro" => micro = true,
+            _ => return Err(format!("unknown bench argument '{}'", arg)),
+        }
+    }
+
+    let positions = bench_positions();
+    if micro {
+        run_micro_benchmarks(&positions);
+        println!();
+    }
+    let (nodes, time) = run_search_benchmark(&positions, depth);
+    println!(
+        "\nTotal: {} nodes in {:.3}s, {:.0} nps",
+        nodes,
+        time.as_secs_f64(),
+        nodes as f64 / time.as_secs_f64().max(1e-9)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_positions_are_playable() {
+        for (name, position) in bench_positions() {
+            assert!(!position.game_over && !legal_moves(&position).is_empty(), "{} has no move to search", name);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::notation::game_from_transcript;
+use crate::{init_board_essentials, start_position, Game, Square};
+use std::path::Path;
+
+// An opening book: lines of play written as transcripts, one per line, e.g. "f5d6c3d3c4". Empty
+// lines and lines starting with '%' or '#' are skipped. While the game follows one of the lines
+// from the starting position, the AI plays the line's next move instead of searching.
+
+pub struct Book {
+    lines: Vec<Vec<Square>>,
+}
+
+pub fn parse_book(text: &str) -> Result<Book, String> {
+    let mut lines = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') || line.starts_with('#') {
+            continue;
+        }
+        let game = game_from_transcript(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        lines.push(game.moves);
+    }
+    Ok(Book { lines })
+}
+
+pub fn load_book(path: &Path) -> Result<Book, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read book {}: {}", path.display(), e))?;
+    parse_book(&text).map_err(|e| format!("book {}: {}", path.display(), e))
+}
+
+impl Book {
+    // The next move of the first line the game is following, if any.
+    pub fn next_move(&self, game: &Game) -> Option<Square> {
+        let start = init_board_essentials();
+        if start_position(game) != (start.board, start.white_turn) {
+            return None;
+        }
+        self.lines
+            .iter()
+            .find(|line| line.len() > game.moves.len() && line.starts_with(&game.moves))
+            .map(|line| line[game.moves.len()].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_first_matching_line() {
+        let book = parse_book("% openings\nc4c3\n\nc4e3f6\n").unwrap();
+        let game = game_from_transcript("c4").unwrap();
+        assert_eq!(book.next_move(&game), Some("c3".parse().unwrap()));
+        let game = game_from_transcript("c4e3").unwrap();
+        assert_eq!(book.next_move(&game), Some("f6".parse().unwrap()));
+        let game = game_from_transcript("c4e3f6").unwrap();
+        assert_eq!(book.next_move(&game), None);
+        assert_eq!(parse_book("c4a1").err(), Some("line 1: move 2: a1 is not a legal move".to_string()));
+    }
+}
@@ -0,0 +1,520 @@
+use crate::minmax::{best_move_within, get_for_whoever_best_move, ranked_moves};
+use crate::savegame::player_name;
+use crate::settings::{book, settings, TimeControl};
+use crate::variations::{branch_point, parse_tree, write_tree, Tree};
+use crate::{
+    board_essentials_from_board, count_stones, do_move, legal_moves, start_position, undo, Colour, Game, Square,
+    BOARD_SIZE,
+};
+use std::time::{Duration, Instant};
+
+// The game being played and everything around it that the front-ends (window, terminal and TUI)
+// share: who plays which side, the AI's search depth, every move tried from the start position as
+// a tree of variations, and the time each side has used.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Player {
+    Human,
+    Ai,
+}
+
+// Black then white, as set with the "ai" setting.
+pub fn default_players() -> [Player; 2] {
+    settings().players
+}
+
+pub struct Controller {
+    pub game: Game,
+    // black then white
+    pub players: [Player; 2],
+    // depth of the hints and evaluations
+    pub depth: usize,
+    // search depths of the AI for black and white
+    pub ai_depths: [usize; 2],
+    pub time_control: Option<TimeControl>,
+    // all the moves tried, see variations.rs
+    tree: Tree,
+    // the path through the tree that undo and redo move along: the moves on the board and the
+    // moves last taken back after them. Playing a move cuts it off there, even if the move was
+    // played before, so only moves taken back can be redone.
+    line: Vec<usize>,
+    // time used by black and white, not counting the turn in progress
+    clock: [Duration; 2],
+    turn_started: Instant,
+}
+
+impl Controller {
+    // The variations written in the game's record are read back, see record().
+    pub fn new(game: Game, players: [Player; 2], depth: usize) -> Controller {
+        let (board, white_turn) = start_position(&game);
+        let start = board_essentials_from_board(board, white_turn);
+        let mut tree = game.info.variations.as_deref().and_then(|text| parse_tree(text, &start).ok()).unwrap_or_default();
+        let mut line = vec![];
+        for square in &game.moves {
+            line.push(tree.add(&line, square.clone()));
+        }
+        Controller {
+            game,
+            players,
+            depth,
+            ai_depths: [depth; 2],
+            time_control: settings().time_control,
+            tree,
+            line,
+            clock: [Duration::ZERO; 2],
+            turn_started: Instant::now(),
+        }
+    }
+
+    // Continues the line along the main line of the tree from where it ends.
+    fn extend_line(&mut self) {
+        let rest = self.tree.continuation(&self.line, &[]);
+        self.line.extend(rest);
+    }
+
+    // The path to the position on the board.
+    fn path(&self) -> &[usize] {
+        &self.line[..self.game.moves.len()]
+    }
+
+    pub fn player_to_move(&self) -> Player {
+        self.players[self.game.board_essential.white_turn as usize]
+    }
+
+    pub fn human_to_move(&self) -> bool {
+        !self.game.board_essential.game_over && self.player_to_move() == Player::Human
+    }
+
+    pub fn ai_to_move(&self) -> bool {
+        !self.game.board_essential.game_over && self.player_to_move() == Player::Ai
+    }
+
+    // The time used by one side, including the turn in progress.
+    pub fn clock(&self, white: bool) -> Duration {
+        let mut time = self.clock[white as usize];
+        if !self.game.board_essential.game_over && self.game.board_essential.white_turn == white {
+            time += self.turn_started.elapsed();
+        }
+        time
+    }
+
+    // The time one side has left under the time control, with the increments for its moves so far.
+    pub fn remaining(&self, white: bool) -> Option<Duration> {
+        let time_control = self.time_control?;
+        let moves = self.game.prev_boards.iter().filter(|(_, white_moved)| *white_moved == white).count() as u32;
+        Some((time_control.base + time_control.increment * moves).saturating_sub(self.clock(white)))
+    }
+
+    // With a time control the AI spends its remaining time evenly over the moves it probably still
+    // has to play, plus most of the increment.
+    fn thinking_time(&self) -> Option<Duration> {
+        let time_control = self.time_control?;
+        let remaining = self.remaining(self.game.board_essential.white_turn)?;
+        let moves_left = (BOARD_SIZE * BOARD_SIZE - self.game.board_essential.amount_of_stone) as u32 / 2 + 1;
+        Some(remaining / moves_left + time_control.increment * 4 / 5)
+    }
+
+    fn stop_clock(&mut self) {
+        if !self.game.board_essential.game_over {
+            self.clock[self.game.board_essential.white_turn as usize] += self.turn_started.elapsed();
+        }
+        self.turn_started = Instant::now();
+    }
+
+    pub fn play(&mut self, square: Square) -> Result<(), String> {
+        if self.game.board_essential.game_over {
+            return Err("the game is over".to_string());
+        }
+        if !legal_moves(&self.game.board_essential).contains(&square) {
+            return Err(format!("{} is not a legal move", square));
+        }
+        self.stop_clock();
+        // a move that was not played here before starts a new variation
+        let ply = self.game.moves.len();
+        let index = self.tree.add(&self.line[..ply], square.clone());
+        self.line.truncate(ply);
+        self.line.push(index);
+        do_move(square.x, square.y, &mut self.game);
+        Ok(())
+    }
+
+    // Plays and returns the AI's move if it is the AI's turn.
+    pub fn ai_move(&mut self) -> Option<Square> {
+        let square = self.ai_search()?()?;
+        self.play(square.clone()).expect("the AI plays legal moves");
+        Some(square)
+    }
+
+    // The AI's search for its move in the current position if it is the AI's turn, to be run on
+    // another thread while the front-end keeps responding. A book move is looked up at once.
+    pub fn ai_search(&self) -> Option<impl FnOnce() -> Option<Square> + Send + 'static> {
+        if !self.ai_to_move() {
+            return None;
+        }
+        let book_move = book().and_then(|book| book.next_move(&self.game));
+        let board_essential = self.game.board_essential.clone();
+        let depth = self.ai_depths[board_essential.white_turn as usize];
+        let time = self.thinking_time();
+        Some(move || match (book_move, time) {
+            (Some(square), _) => Some(square),
+            (None, Some(time)) => best_move_within(&board_essential, depth, time),
+            (None, None) => get_for_whoever_best_move(&board_essential, depth),
+        })
+    }
+
+    // Ends the game with a win for the other side.
+    pub fn resign(&mut self, white: bool) {
+        if self.game.board_essential.game_over {
+            return;
+        }
+        self.stop_clock();
+        self.game.board_essential.game_over = true;
+        self.game.board_essential.winner = if white { Colour::BLACK } else { Colour::WHITE };
+        // as on the game servers, the winner of a resigned game gets all the discs
+        // (GGF results are black's disc margin)
+        let discs = (BOARD_SIZE * BOARD_SIZE) as f64;
+        self.game.info.result = Some(format!("{:+.3}:r", if white { discs } else { -discs }));
+    }
+
+    // The best `count` moves for the side to move with their values, see minmax::ranked_moves. The
+    // hint is counted in the game's record.
+    pub fn take_hint(&mut self, count: usize) -> Vec<(Square, isize)> {
+        let board_essential = &self.game.board_essential;
+        if board_essential.game_over {
+            return vec![];
+        }
+        self.game.info.hints[board_essential.white_turn as usize] += 1;
+        let mut moves = ranked_moves(board_essential, self.depth);
+        moves.truncate(count);
+        moves
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.line.len() > self.game.moves.len()
+    }
+
+    fn resigned(&self) -> bool {
+        let result = self.game.info.result.as_deref();
+        self.game.board_essential.game_over && result.is_some_and(|result| result.ends_with(":r"))
+    }
+
+    // Takes back the last move, or the resignation that ended the game.
+    pub fn undo(&mut self) -> bool {
+        if self.resigned() {
+            self.game.board_essential.game_over = false;
+            self.game.board_essential.winner = Colour::EMPTY;
+            self.game.info.result = None;
+            self.turn_started = Instant::now();
+            return true;
+        }
+        if self.game.moves.is_empty() {
+            return false;
+        }
+        // the result of a loaded game stays unless the game is reopened
+        let reopened = self.game.board_essential.game_over;
+        self.stop_clock();
+        undo(&mut self.game);
+        if reopened {
+            self.game.info.result = None;
+        }
+        true
+    }
+
+    // Replays the move last taken back here.
+    pub fn redo(&mut self) -> bool {
+        let Some(&index) = self.line.get(self.game.moves.len()) else {
+            return false;
+        };
+        let square = self.tree.children(self.path())[index].square.clone();
+        self.stop_clock();
+        do_move(square.x, square.y, &mut self.game);
+        true
+    }
+
+    // Takes back moves until a human is to move again, so the AI's replies go as well. Nothing is
+    // taken back if no human has moved yet; without human players, or after a resignation, only
+    // one move (or the resignation) is.
+    pub fn undo_to_human(&mut self) -> bool {
+        if !self.players.contains(&Player::Human) || self.resigned() {
+            return self.undo();
+        }
+        let moves = self.game.moves.len();
+        while self.undo() {
+            if self.player_to_move() == Player::Human {
+                return true;
+            }
+        }
+        self.go_to(moves);
+        false
+    }
+
+    // The moves along the line to its end, the ones on the board first, as record() plays them.
+    pub fn line_moves(&self) -> Vec<Square> {
+        let mut children = self.tree.children(&[]);
+        let mut moves = vec![];
+        for &index in &self.line {
+            moves.push(children[index].square.clone());
+            children = &children[index].children;
+        }
+        moves
+    }
+
+    // The number of moves played and taken back, which go_to can move between.
+    pub fn history_len(&self) -> usize {
+        self.line.len()
+    }
+
+    // Takes back or replays moves until `ply` moves are on the board.
+    pub fn go_to(&mut self, ply: usize) {
+        while self.game.moves.len() > ply && self.undo() {}
+        while self.game.moves.len() < ply && self.redo() {}
+    }
+
+    // The number of the variation the last move is in and how many there are there, if more than one.
+    pub fn variation(&self) -> Option<(usize, usize)> {
+        let (&index, parent) = self.path().split_last()?;
+        let count = self.tree.children(parent).len();
+        (count > 1).then_some((index + 1, count))
+    }
+
+    // Plays the next (or previous) move instead of the last one.
+    pub fn switch_variation(&mut self, forward: bool) -> bool {
+        let ply = self.game.moves.len();
+        let Some((number, count)) = self.variation() else {
+            return false;
+        };
+        let index = if forward { number % count } else { (number + count - 2) % count };
+        self.undo();
+        self.line.truncate(ply - 1);
+        self.line.push(index);
+        self.extend_line();
+        self.redo()
+    }
+
+    // Makes the variation the position is in the main line where it branched off.
+    pub fn promote_variation(&mut self) -> bool {
+        let path = self.path().to_vec();
+        match self.tree.promote(&path) {
+            Some(ply) => {
+                self.line[ply] = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Goes back to where the variation the position is in branched off and removes it.
+    pub fn delete_variation(&mut self) -> bool {
+        let Some(ply) = branch_point(self.path()) else {
+            return false;
+        };
+        self.go_to(ply);
+        self.tree.remove(&self.line[..ply + 1]);
+        self.line.truncate(ply);
+        self.extend_line();
+        true
+    }
+
+    // The game along the current line to its end, with all the variations, the players and the
+    // result in its record.
+    pub fn record(&self) -> Game {
+        let mut game = self.game.clone();
+        let mut path = self.path().to_vec();
+        for &index in &self.line[self.game.moves.len()..] {
+            let square = &self.tree.children(&path)[index].square;
+            do_move(square.x, square.y, &mut game);
+            path.push(index);
+        }
+        let info = &mut game.info;
+        info.variations = self.tree.has_variations().then(|| write_tree(&self.tree));
+        info.black_player.get_or_insert_with(|| player_name(self.players[0], self.ai_depths[0]));
+        info.white_player.get_or_insert_with(|| player_name(self.players[1], self.ai_depths[1]));
+        if game.board_essential.game_over && info.result.is_none() {
+            // the empty squares count for the winner, as in the official rules
+            let (black, white) = count_stones(&game.board_essential.board);
+            let empties = (BOARD_SIZE * BOARD_SIZE - black - white) as isize;
+            let margin = black as isize - white as isize;
+            info.result = Some(format!("{:+.3}", (margin + margin.signum() * empties) as f64));
+        }
+        game
+    }
+
+    // Replays moves until a human is to move again or there is nothing left to redo.
+    pub fn redo_to_human(&mut self) -> bool {
+        if !self.redo() {
+            return false;
+        }
+        while self.player_to_move() == Player::Ai && self.can_redo() {
+            self.redo();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ggf::{parse_ggf, write_ggf, GgfRecord};
+    use crate::init_game;
+    use crate::notation::transcript;
+
+    #[test]
+    fn undo_and_redo_skip_the_ai_replies() {
+        let mut controller = Controller::new(init_game(), [Player::Ai, Player::Human], 1);
+        controller.play("c4".parse().unwrap()).unwrap();
+        let reply = controller.ai_move().unwrap();
+        assert_eq!(controller.game.moves.len(), 2);
+        assert!(controller.undo_to_human());
+        assert!(controller.game.moves.is_empty());
+        assert!(controller.human_to_move());
+        assert!(controller.redo_to_human());
+        assert_eq!(controller.game.moves, vec!["c4".parse().unwrap(), reply]);
+        assert!(!controller.can_redo());
+    }
+
+    #[test]
+    fn a_new_move_clears_the_moves_to_redo() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        controller.play("c4".parse().unwrap()).unwrap();
+        controller.undo();
+        assert!(controller.can_redo());
+        assert!(controller.play("a1".parse().unwrap()).is_err());
+        controller.play("d3".parse().unwrap()).unwrap();
+        assert!(!controller.can_redo());
+        assert!(!controller.redo());
+    }
+
+    #[test]
+    fn replaying_a_move_taken_back_lets_the_ai_reply() {
+        let mut controller = Controller::new(init_game(), [Player::Ai, Player::Human], 1);
+        controller.play("c4".parse().unwrap()).unwrap();
+        controller.ai_move().unwrap();
+        assert!(controller.undo_to_human());
+        assert!(controller.can_redo());
+        controller.play("c4".parse().unwrap()).unwrap();
+        assert!(!controller.can_redo());
+        assert_eq!(controller.history_len(), 1);
+        assert!(controller.ai_move().is_some());
+        assert_eq!(controller.record().info.variations, None);
+    }
+
+    #[test]
+    fn history_can_be_walked_in_both_directions() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        for square in ["c4", "c3", "c2", "b4"] {
+            controller.play(square.parse().unwrap()).unwrap();
+        }
+        let end = controller.game.clone();
+        controller.go_to(0);
+        assert!(controller.game.moves.is_empty());
+        assert!(controller.game.flipped_tiles_from_move.is_empty());
+        controller.go_to(2);
+        assert_eq!(controller.game.last_placed, "c3".parse().unwrap());
+        assert_eq!(controller.history_len(), 4);
+        controller.go_to(controller.history_len());
+        assert_eq!(controller.game.board_essential.board, end.board_essential.board);
+        assert_eq!(controller.game.last_placed, end.last_placed);
+        assert_eq!(controller.game.flipped_tiles_from_move, end.flipped_tiles_from_move);
+    }
+
+    #[test]
+    fn undo_restores_the_highlighting_and_the_end_of_the_game() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        let mut games = vec![controller.game.clone()];
+        // the shortest game, in which the side moving first wipes out the other
+        for square in "c4c3c2b4a5f4g4c5d6".as_bytes().chunks(2) {
+            controller.play(std::str::from_utf8(square).unwrap().parse().unwrap()).unwrap();
+            games.push(controller.game.clone());
+        }
+        assert!(controller.game.board_essential.game_over);
+        for before in games.iter().rev().skip(1) {
+            controller.undo();
+            assert_eq!(controller.game.board_essential.board, before.board_essential.board);
+            assert_eq!(controller.game.last_placed, before.last_placed);
+            assert_eq!(controller.game.flipped_tiles_from_move, before.flipped_tiles_from_move);
+            assert!(!controller.game.board_essential.game_over);
+        }
+        controller.go_to(controller.history_len());
+        assert!(controller.game.board_essential.game_over);
+        assert_eq!(controller.game.board_essential.winner, Colour::WHITE);
+        // white wipes out black with 13 discs and gets the empty squares too
+        assert_eq!(controller.record().info.result.as_deref(), Some("-64.000"));
+    }
+
+    #[test]
+    fn undo_takes_back_a_resignation_and_keeps_a_loaded_result() {
+        let mut controller = Controller::new(init_game(), [Player::Ai, Player::Human], 1);
+        controller.play("c4".parse().unwrap()).unwrap();
+        controller.ai_move().unwrap();
+        controller.resign(true);
+        assert!(controller.undo_to_human());
+        assert_eq!(controller.game.moves.len(), 2);
+        assert!(!controller.game.board_essential.game_over);
+        assert_eq!(controller.game.info.result, None);
+        assert!(controller.human_to_move());
+
+        controller.game.info.result = Some("+12.000".to_string());
+        controller.undo();
+        assert_eq!(controller.game.moves.len(), 1);
+        assert_eq!(controller.game.info.result.as_deref(), Some("+12.000"));
+    }
+
+    #[test]
+    fn undo_to_human_keeps_the_ai_opening_move() {
+        // the AI moves first; before the human's first move there is nothing of theirs to take back
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Ai], 1);
+        controller.ai_move().unwrap();
+        assert!(!controller.undo_to_human());
+        assert_eq!(controller.game.moves.len(), 1);
+        assert!(!controller.can_redo());
+    }
+
+    #[test]
+    fn variations_are_kept_promoted_and_deleted() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        for square in ["c4", "c3", "c2"] {
+            controller.play(square.parse().unwrap()).unwrap();
+        }
+        controller.go_to(1);
+        controller.play("e3".parse().unwrap()).unwrap();
+        controller.play("f4".parse().unwrap()).unwrap();
+        assert_eq!(controller.variation(), None);
+        controller.undo();
+        assert_eq!(controller.variation(), Some((2, 2)));
+        assert!(controller.switch_variation(true));
+        assert_eq!(controller.game.moves, vec!["c4".parse().unwrap(), "c3".parse().unwrap()]);
+        // redo goes back into the main line
+        assert_eq!(controller.history_len(), 3);
+        assert!(controller.switch_variation(false));
+        assert_eq!(controller.history_len(), 3);
+        assert_eq!(transcript(&controller.record()), "c4e3f4");
+        assert_eq!(controller.record().info.variations.as_deref(), Some("c4c3(e3f4)c2"));
+
+        assert!(controller.promote_variation());
+        assert_eq!(controller.variation(), Some((1, 2)));
+        assert_eq!(controller.record().info.variations.as_deref(), Some("c4e3(c3c2)f4"));
+        controller.switch_variation(true);
+        assert!(controller.delete_variation());
+        assert_eq!(controller.game.moves.len(), 1);
+        assert_eq!(controller.record().info.variations, None);
+        assert_eq!(transcript(&controller.record()), "c4e3f4");
+    }
+
+    #[test]
+    fn variations_survive_a_ggf_round_trip() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        for square in ["c4", "c3", "c2"] {
+            controller.play(square.parse().unwrap()).unwrap();
+        }
+        controller.undo();
+        controller.play("e6".parse().unwrap()).unwrap();
+        let text = write_ggf(&GgfRecord::from_game(&controller.record()));
+        assert!(text.contains("VA[c4c3c2(e6)]"), "{}", text);
+        let game = parse_ggf(&text).unwrap().remove(0).game;
+        let mut reloaded = Controller::new(game, [Player::Human, Player::Human], 1);
+        assert_eq!(reloaded.variation(), Some((2, 2)));
+        assert!(reloaded.switch_variation(true));
+        assert_eq!(transcript(&reloaded.game), "c4c3c2");
+        assert!(parse_ggf(&text.replace("VA[c4c3c2(e6)]", "VA[c4c3c2(a1)]")).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::settings::settings;
+use crate::{BoardEssentials, Colour, Square, BOARD_SIZE};
+
+// Exact endgame solver. Searching to the end of the game needs far more nodes than the
+// evaluation-based search in minmax.rs, so it works on a compact copy of the position: one
+// bitboard for the discs of the side to move and one for the opponent, bit y * 8 + x for square
+// (x, y). Scores are final disc differences for the side to move, with the empty squares counted
+// for the winner as in tournament play.
+
+#[derive(Clone, Copy)]
+struct Position {
+    player: u64,
+    opponent: u64,
+}
+
+pub struct EndgameResult {
+    // None if the game is already over
+    pub best: Option<Square>,
+    pub score: isize,
+    pub nodes: u64,
+}
+
+const NOT_A_FILE: u64 = 0xfefe_fefe_fefe_fefe;
+const NOT_H_FILE: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+// (shift, mask applied after shifting) for the eight directions
+const DIRECTIONS: [(i32, u64); 8] = [
+    (1, NOT_A_FILE),
+    (-1, NOT_H_FILE),
+    (8, u64::MAX),
+    (-8, u64::MAX),
+    (9, NOT_A_FILE),
+    (7, NOT_H_FILE),
+    (-7, NOT_A_FILE),
+    (-9, NOT_H_FILE),
+];
+
+fn shift(bits: u64, direction: (i32, u64)) -> u64 {
+    let (amount, mask) = direction;
+    if amount > 0 {
+        (bits << amount) & mask
+    } else {
+        (bits >> -amount) & mask
+    }
+}
+
+impl Position {
+    fn from_board_essentials(board_essential: &BoardEssentials) -> Position {
+        let (mut white, mut black) = (0u64, 0u64);
+        for (x, column) in board_essential.board.iter().enumerate() {
+            for (y, colour) in column.iter().enumerate() {
+                let bit = 1u64 << (y * BOARD_SIZE + x);
+                match colour {
+                    Colour::WHITE => white |= bit,
+                    Colour::BLACK => black |= bit,
+                    Colour::EMPTY => (),
+                }
+            }
+        }
+        if board_essential.white_turn {
+            Position { player: white, opponent: black }
+        } else {
+            Position { player: black, opponent: white }
+        }
+    }
+
+    fn empty(&self) -> u64 {
+        !(self.player | self.opponent)
+    }
+
+    fn moves(&self) -> u64 {
+        let empty = self.empty();
+        let mut moves = 0;
+        for direction in DIRECTIONS {
+            let mut candidates = shift(self.player, direction) & self.opponent;
+            for _ in 0..5 {
+                candidates |= shift(candidates, direction) & self.opponent;
+            }
+            moves |= shift(candidates, direction) & empty;
+        }
+        moves
+    }
+
+    fn flips(&self, square: u64) -> u64 {
+        let mut flips = 0;
+        for direction in DIRECTIONS {
+            let mut line = 0;
+            let mut current = shift(square, direction);
+            while current & self.opponent != 0 {
+                line |= current;
+                current = shift(current, direction);
+            }
+            if current & self.player != 0 {
+                flips |= line;
+            }
+        }
+        flips
+    }
+
+    // The position after playing `square`, seen from the opponent.
+    fn play(&self, square: u64) -> Position {
+        let flips = self.flips(square);
+        Position {
+            player: self.opponent & !flips,
+            opponent: self.player | flips | square,
+        }
+    }
+
+    fn pass(&self) -> Position {
+        Position { player: self.opponent, opponent: self.player }
+    }
+
+    fn final_score(&self) -> isize {
+        let player = self.player.count_ones() as isize;
+        let opponent = self.opponent.count_ones() as isize;
+        let empty = 64 - player - opponent;
+        if player > opponent {
+            player - opponent + empty
+        } else if player < opponent {
+            player - opponent - empty
+        } else {
+            0
+        }
+    }
+}
+
+// Positions with at least this many empty squares go into the transposition table; below that
+// the search is cheaper than the lookup.
+const HASH_MIN_EMPTY: u32 = 8;
+
+// Bounds on the score of a position found by an earlier search, and the move that was best or
+// caused the cutoff.
+#[derive(Clone, Copy)]
+struct HashEntry {
+    player: u64,
+    opponent: u64,
+    lower: i8,
+    upper: i8,
+    best: u8,
+}
+
+const NO_MOVE: u8 = 64;
+
+struct Solver {
+    nodes: u64,
+    // the table has 2^hash_bits entries
+    table: Vec<Option<HashEntry>>,
+    hash_bits: u32,
+}
+
+impl Solver {
+    // A transposition table as large as the hash_mb setting allows.
+    fn new() -> Solver {
+        let entries = settings().hash_mb * (1 << 20) / std::mem::size_of::<Option<HashEntry>>();
+        let hash_bits = entries.max(2).ilog2();
+        Solver { nodes: 0, table: vec![None; 1 << hash_bits], hash_bits }
+    }
+
+    fn hash_index(&self, position: &Position) -> usize {
+        let hash =
+            position.player.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ position.opponent.wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        (hash >> (64 - self.hash_bits)) as usize
+    }
+
+    fn lookup(&self, position: &Position) -> Option<HashEntry> {
+        self.table[self.hash_index(position)]
+            .filter(|entry| entry.player == position.player && entry.opponent == position.opponent)
+    }
+
+    fn store(&mut self, position: &Position, alpha: isize, beta: isize, score: isize, best: u8) {
+        let (mut lower, mut upper) = (-64, 64);
+        if let Some(entry) = self.lookup(position) {
+            (lower, upper) = (entry.lower, entry.upper);
+        }
+        if score > alpha {
+            lower = lower.max(score as i8);
+        }
+        if score < beta {
+            upper = upper.min(score as i8);
+        }
+        let entry = HashEntry { player: position.player, opponent: position.opponent, lower, upper, best };
+        let index = self.hash_index(position);
+        self.table[index] = Some(entry);
+    }
+
+    // Fail-soft alpha-beta with null windows after the first move (principal variation search)
+    // over the final disc difference.
+    fn negamax(&mut self, position: Position, mut alpha: isize, mut beta: isize, passed: bool) -> isize {
+        self.nodes += 1;
+        let moves = position.moves();
+        if moves == 0 {
+            if passed {
+                return position.final_score();
+            }
+            return -self.negamax(position.pass(), -beta, -alpha, true);
+        }
+
+        let empty = position.empty().count_ones();
+        let mut hash_move = NO_MOVE;
+        if empty >= HASH_MIN_EMPTY {
+            if let Some(entry) = self.lookup(&position) {
+                let (lower, upper) = (entry.lower as isize, entry.upper as isize);
+                if lower >= beta || lower == upper {
+                    return lower;
+                }
+                if upper <= alpha {
+                    return upper;
+                }
+                alpha = alpha.max(lower);
+                beta = beta.min(upper);
+                hash_move = entry.best;
+            }
+        }
+
+        let (alpha_in, beta_in) = (alpha, beta);
+        let (mut best_score, mut best_move) = (-65, NO_MOVE);
+        for (i, square) in self.ordered_moves(&position, moves, hash_move).into_iter().enumerate() {
+            let next = position.play(square);
+            let mut score = if i == 0 {
+                -self.negamax(next, -beta, -alpha, false)
+            } else {
+                -self.negamax(next, -alpha - 1, -alpha, false)
+            };
+            if i > 0 && score > alpha && score < beta {
+                score = -self.negamax(next, -beta, -score, false);
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = square.trailing_zeros() as u8;
+                if score >= beta {
+                    break;
+                }
+                alpha = alpha.max(score);
+            }
+        }
+        if empty >= HASH_MIN_EMPTY {
+            self.store(&position, alpha_in, beta_in, best_score, best_move);
+        }
+        best_score
+    }
+
+    // With many empty squares, try the move from the transposition table first and then the
+    // moves that leave the opponent the fewest replies, corners before the rest.
+    fn ordered_moves(&self, position: &Position, moves: u64, hash_move: u8) -> Vec<u64> {
+        let mut squares: Vec<u64> = (0..64).map(|i| 1u64 << i).filter(|bit| moves & bit != 0).collect();
+        if position.empty().count_ones() > 6 {
+            const CORNERS: u64 = 0x8100_0000_0000_0081;
+            squares.sort_by_cached_key(|square| {
+                if square.trailing_zeros() as u8 == hash_move {
+                    return -1;
+                }
+                let replies = position.play(*square).moves();
+                2 * (replies.count_ones() + (replies & CORNERS).count_ones()) as i32 - (square & CORNERS != 0) as i32
+            });
+        }
+        squares
+    }
+}
+
+pub fn solve(board_essential: &BoardEssentials) -> EndgameResult {
+    let mut solver = Solver::new();
+    let position = Position::from_board_essentials(board_essential);
+    if board_essential.game_over {
+        return EndgameResult { best: None, score: position.final_score(), nodes: 0 };
+    }
+
+    let score = solver.negamax(position, -64, 64, false);
+    // the root is always stored, unless there are too few empty squares for the table
+    let best = match solver.lookup(&position) {
+        Some(entry) if entry.best != NO_MOVE => entry.best as usize,
+        _ => best_move_by_search(&mut solver, &position, score),
+    };
+    let best = Some(Square { x: best % BOARD_SIZE, y: best / BOARD_SIZE });
+    EndgameResult { best, score, nodes: solver.nodes }
+}
+
+fn best_move_by_search(solver: &mut Solver, position: &Position, score: isize) -> usize {
+    let moves = position.moves();
+    for square in solver.ordered_moves(position, moves, NO_MOVE) {
+        if -solver.negamax(position.play(square), -score, -score + 1, false) >= score {
+            return square.trailing_zeros() as usize;
+        }
+    }
+    unreachable!("one of the moves reaches the score of the position")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{do_move_essentials, init_board_essentials, legal_moves};
+
+    // Plain minimax over BoardEssentials as a reference for the bitboard solver.
+    fn reference_score(board_essential: &BoardEssentials) -> isize {
+        if board_essential.game_over {
+            return Position::from_board_essentials(board_essential).final_score();
+        }
+        legal_moves(board_essential)
+            .iter()
+            .map(|sq| {
+                let mut clone = board_essential.clone();
+                do_move_essentials(sq.x, sq.y, &mut clone);
+                let score = reference_score(&clone);
+                if clone.white_turn == board_essential.white_turn {
+                    score
+                } else {
+                    -score
+                }
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn moves_match_move_generation() {
+        for (_, position) in crate::bench::bench_positions() {
+            let expected: u64 = legal_moves(&position)
+                .iter()
+                .map(|sq| 1u64 << (sq.y * BOARD_SIZE + sq.x))
+                .sum();
+            assert_eq!(Position::from_board_essentials(&position).moves(), expected);
+        }
+    }
+
+    #[test]
+    fn solves_small_endgames_like_minimax() {
+        // play a few games out to seven empty squares, picking moves by a fixed pattern
+        for seed in 0..4 {
+            let mut position = init_board_essentials();
+            let mut ply = 0;
+            while position.amount_of_stone < 57 && !position.game_over {
+                let moves = legal_moves(&position);
+                let sq = &moves[(ply * 7 + seed * 3) % moves.len()];
+                do_move_essentials(sq.x, sq.y, &mut position);
+                ply += 1;
+            }
+            if position.game_over {
+                continue;
+            }
+            let result = solve(&position);
+            assert_eq!(result.score, reference_score(&position), "{}", position);
+            let best = result.best.unwrap();
+            let mut after = position.clone();
+            do_move_essentials(best.x, best.y, &mut after);
+            let score = reference_score(&after);
+            let score = if after.white_turn == position.white_turn { score } else { -score };
+            assert_eq!(score, result.score, "{}: {} is not a best move", position, best);
+        }
+    }
+}
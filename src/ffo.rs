@@ -0,0 +1,190 @@
+use crate::endgame::solve;
+use crate::{BoardEssentials, Square};
+use std::time::{Duration, Instant};
+
+// FFO endgame test positions: positions from the French Othello Federation's forum with known
+// exact results, used to check the endgame solver and to measure its speed. Positions #40 and #41
+// are bundled, other FFO files can be given with --file. The files are in OBF format, one position
+// per line: the position, then every best move with the final disc difference for the side to
+// move, e.g. "<position> X; A2:+38; B1:+38;".
+// Lines starting with '%' are comments.
+
+const BUNDLED: &str = include_str!("../data/ffo-40-41.obf");
+const BUNDLED_FIRST_NUMBER: usize = 40;
+
+pub struct FfoPosition {
+    pub number: usize,
+    pub position: BoardEssentials,
+    pub best_moves: Vec<Square>,
+    pub score: isize,
+}
+
+// Entries are numbered in file order starting at `first_number`.
+pub fn parse_ffo(text: &str, first_number: usize) -> Result<Vec<FfoPosition>, String> {
+    let mut positions = vec![];
+    let lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('%'));
+    for (line_number, line) in lines {
+        let error = |message: String| format!("line {}: {}", line_number + 1, message);
+        let position: BoardEssentials = line.parse().map_err(|e| error(format!("{}", e)))?;
+        let mut best_moves = vec![];
+        let mut score = None;
+        for answer in line.split(';').skip(1).map(str::trim).filter(|answer| !answer.is_empty()) {
+            let (square, value) = answer
+                .split_once(':')
+                .ok_or_else(|| error(format!("'{}' is not a move and score", answer)))?;
+            let square: Square = square.parse().map_err(|e| error(format!("{}", e)))?;
+            let value: isize = value
+                .trim_start_matches('+')
+                .parse()
+                .map_err(|_| error(format!("'{}' is not a score", value)))?;
+            // only the moves reaching the best score count as answers
+            match score {
+                Some(best) if value < best => continue,
+                Some(best) if value == best => best_moves.push(square),
+                _ => {
+                    score = Some(value);
+                    best_moves = vec![square];
+                }
+            }
+        }
+        let score = score.ok_or_else(|| error("no best move given".to_string()))?;
+        positions.push(FfoPosition { number: first_number + positions.len(), position, best_moves, score });
+    }
+    Ok(positions)
+}
+
+pub fn bundled_positions() -> Vec<FfoPosition> {
+    parse_ffo(BUNDLED, BUNDLED_FIRST_NUMBER).expect("the bundled FFO positions are valid")
+}
+
+pub struct FfoResult {
+    pub best: Option<Square>,
+    pub score: isize,
+    pub nodes: u64,
+    pub time: Duration,
+    pub correct: bool,
+}
+
+pub fn run_position(ffo: &FfoPosition) -> FfoResult {
+    let start = Instant::now();
+    let result = solve(&ffo.position);
+    let time = start.elapsed();
+    let correct = result.score == ffo.score && result.best.as_ref().is_some_and(|best| ffo.best_moves.contains(best));
+    FfoResult { best: result.best, score: result.score, nodes: result.nodes, time, correct }
+}
+
+fn show_moves(moves: &[Square]) -> String {
+    moves.iter().map(|sq| sq.to_string()).collect::<Vec<String>>().join(",")
+}
+
+// Usage: othello ffo [--file <obf file>] [--first N] [--last N]
+// Without --file the bundled positions #40 and #41 are solved, otherwise the entries of the file are
+// numbered from 1.
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let mut positions = None;
+    let (mut first, mut last) = (0, usize::MAX);
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value for {}", name)).cloned();
+        match arg.as_str() {
+            "--file" => {
+                let path = value("--file")?;
+                let text = std::fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+                positions = Some(parse_ffo(&text, 1).map_err(|e| format!("{}: {}", path, e))?);
+            }
+            "--first" | "--last" => {
+                let text = value(arg)?;
+                let number = text.parse().map_err(|_| format!("{} expects a number, got '{}'", arg, text))?;
+                if arg == "--first" {
+                    first = number;
+                } else {
+                    last = number;
+                }
+            }
+            _ => return Err(format!("unknown ffo argument '{}'", arg)),
+        }
+    }
+    let positions: Vec<FfoPosition> = positions
+        .unwrap_or_else(bundled_positions)
+        .into_iter()
+        .filter(|ffo| ffo.number >= first && ffo.number <= last)
+        .collect();
+    if positions.is_empty() {
+        return Err("no positions to solve".to_string());
+    }
+
+    println!(" #  empty  expected     found      time         nodes          nps");
+    let (mut total_nodes, mut total_time, mut failures) = (0, Duration::ZERO, vec![]);
+    for ffo in &positions {
+        let result = run_position(ffo);
+        let empty = 64 - ffo.position.amount_of_stone;
+        let found = result.best.map_or("--".to_string(), |sq| sq.to_string());
+        println!(
+            "{:>2}  {:>5}  {:>3} {:+3}   {:>3} {:+3}  {:>8.3}s {:>13} {:>12.0}{}",
+            ffo.number,
+            empty,
+            show_moves(&ffo.best_moves),
+            ffo.score,
+            found,
+            result.score,
+            result.time.as_secs_f64(),
+            result.nodes,
+            result.nodes as f64 / result.time.as_secs_f64().max(1e-9),
+            if result.correct { "" } else { "  WRONG" }
+        );
+        total_nodes += result.nodes;
+        total_time += result.time;
+        if !result.correct {
+            failures.push(ffo.number);
+        }
+    }
+    println!(
+        "\n{} positions: {} nodes in {:.3}s, {:.0} nps",
+        positions.len(),
+        total_nodes,
+        total_time.as_secs_f64(),
+        total_nodes as f64 / total_time.as_secs_f64().max(1e-9)
+    );
+    if !failures.is_empty() {
+        return Err(format!("wrong result for #{}", failures.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", #")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_positions_parse() {
+        let positions = bundled_positions();
+        assert!(!positions.is_empty());
+        for ffo in &positions {
+            assert!(!ffo.position.game_over, "#{} is already over", ffo.number);
+            assert!(!ffo.best_moves.is_empty());
+        }
+    }
+
+    #[test]
+    #[ignore = "takes minutes in debug builds, run with --release --ignored"]
+    fn solves_the_first_bundled_positions() {
+        for ffo in bundled_positions().iter().filter(|ffo| ffo.number <= 41) {
+            let result = run_position(ffo);
+            assert!(result.correct, "#{}: {:?} {:+}", ffo.number, result.best.map(|sq| sq.to_string()), result.score);
+        }
+        let numbers: Vec<usize> = bundled_positions().iter().map(|ffo| ffo.number).collect();
+        assert_eq!(numbers[..2], [40, 41]);
+    }
+
+    #[test]
+    fn reads_answers() {
+        let text = "% comment\n---------------------------OX------XO--------------------------- X; F5:+0; D3:+0; C4:-2;\n";
+        let positions = parse_ffo(text, 1).unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].number, 1);
+        assert_eq!(positions[0].score, 0);
+        assert_eq!(positions[0].best_moves, vec!["f5".parse().unwrap(), "d3".parse().unwrap()]);
+        assert!(parse_ffo("---------------------------OX------XO--------------------------- X;", 1).is_err());
+        assert!(parse_ffo("---------------------------OX------XO--------------------------- X; F5:x;", 1).is_err());
+    }
+}
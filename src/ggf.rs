@@ -0,0 +1,351 @@
+use crate::{
+    board_essentials_from_board, do_move, game_from_board_essentials, legal_moves, start_position, Colour, Game,
+    GameInfo, Square, BOARD_SIZE,
+};
+use crate::variations::parse_tree;
+use std::fmt;
+
+// The Generic Game Format used by the Othello servers, e.g.
+// (;GM[Othello]PC[NOS]DT[2003.12.15]PB[alice]PW[bob]RB[1850.2]RW[1790.4]TI[15:00//02:00]TY[8]
+//   RE[+18.000]BO[8 ---------------------------O*------*O--------------------------- *]
+//   B[f5//0.02]W[f6/-1.50/1.10]B[e6]...;)
+// The board in BO is written row by row from a1 to h8 with '*' for black, 'O' for white and '-'
+// for empty, followed by the side to move. A move is a square or "PA" for a pass, optionally
+// followed by the mover's evaluation and the seconds it took, separated by '/'.
+// Games saved here may also have a VA tag with the variations that were tried and an HI tag with the
+// number of hints black and white asked for, e.g. HI[2 0], which other programs ignore.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GgfMove {
+    // None is a pass
+    pub square: Option<Square>,
+    pub eval: Option<f64>,
+    pub time: Option<f64>,
+}
+
+#[derive(Clone)]
+pub struct GgfRecord {
+    pub game: Game,
+    // every move of the game in order, passes included
+    pub moves: Vec<GgfMove>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GgfError {
+    Syntax(String),
+    UnsupportedBoard(String),
+    InvalidPosition(String),
+    InvalidMove { move_number: usize, text: String },
+    IllegalMove { move_number: usize, text: String },
+    WrongColour { move_number: usize },
+}
+
+impl fmt::Display for GgfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GgfError::Syntax(message) => write!(f, "GGF syntax error: {}", message),
+            GgfError::UnsupportedBoard(board_type) => write!(f, "unsupported board type '{}', only 8x8 is", board_type),
+            GgfError::InvalidPosition(position) => write!(f, "invalid start position '{}'", position),
+            GgfError::InvalidMove { move_number, text } => write!(f, "move {}: cannot read '{}'", move_number, text),
+            GgfError::IllegalMove { move_number, text } => write!(f, "move {}: {} is not legal", move_number, text),
+            GgfError::WrongColour { move_number } => write!(f, "move {}: played by the side not to move", move_number),
+        }
+    }
+}
+
+impl GgfRecord {
+    // The record of a game played in this program, with the passes that happened spelled out.
+    pub fn from_game(game: &Game) -> GgfRecord {
+        let mut moves = vec![];
+        for (i, square) in game.moves.iter().enumerate() {
+            moves.push(GgfMove { square: Some(square.clone()), eval: None, time: None });
+            let mover = game.prev_boards[i].1;
+            let next_to_move = match game.prev_boards.get(i + 1) {
+                Some(next) => next.1,
+                None if game.board_essential.game_over => !mover,
+                None => game.board_essential.white_turn,
+            };
+            if next_to_move == mover {
+                moves.push(GgfMove { square: None, eval: None, time: None });
+            }
+        }
+        GgfRecord { game: game.clone(), moves }
+    }
+}
+
+// Reads every game in `text`.
+pub fn parse_ggf(text: &str) -> Result<Vec<GgfRecord>, GgfError> {
+    let mut records = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find("(;") {
+        let end = rest[start..]
+            .find(";)")
+            .ok_or_else(|| GgfError::Syntax("game is not closed with ';)'".to_string()))?;
+        records.push(parse_game(&rest[start + 2..start + end])?);
+        rest = &rest[start + end + 2..];
+    }
+    if records.is_empty() {
+        return Err(GgfError::Syntax("no game found, expected '(;'".to_string()));
+    }
+    Ok(records)
+}
+
+// Splits `GM[Othello]PB[name]...` into (tag, value) pairs.
+fn parse_properties(text: &str) -> Result<Vec<(String, String)>, GgfError> {
+    let mut properties = vec![];
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let open = rest
+            .find('[')
+            .ok_or_else(|| GgfError::Syntax(format!("expected '[' after '{}'", rest)))?;
+        let close = rest[open..]
+            .find(']')
+            .ok_or_else(|| GgfError::Syntax(format!("missing ']' for '{}'", &rest[..open])))?;
+        let tag = rest[..open].trim();
+        if tag.is_empty() || !tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(GgfError::Syntax(format!("invalid tag '{}'", tag)));
+        }
+        properties.push((tag.to_string(), rest[open + 1..open + close].to_string()));
+        rest = rest[open + close + 1..].trim_start();
+    }
+    Ok(properties)
+}
+
+fn parse_game(text: &str) -> Result<GgfRecord, GgfError> {
+    let mut info = GameInfo::default();
+    let mut game: Option<Game> = None;
+    let mut moves = vec![];
+    // moves before BO are applied once the start position is known
+    let mut pending_moves: Vec<(bool, String)> = vec![];
+
+    for (tag, value) in parse_properties(text)? {
+        match tag.as_str() {
+            "GM" if !value.eq_ignore_ascii_case("othello") => {
+                return Err(GgfError::Syntax(format!("not an Othello game: GM[{}]", value)));
+            }
+            "PB" => info.black_player = Some(value),
+            "PW" => info.white_player = Some(value),
+            "RB" => info.black_rating = value.parse().ok(),
+            "RW" => info.white_rating = value.parse().ok(),
+            "TI" => info.time_control = Some(value),
+            "PC" => info.place = Some(value),
+            "DT" => info.date = Some(value),
+            "RE" => info.result = Some(value),
+            // not part of GGF: the moves tried besides the game, see variations.rs
+            "VA" => info.variations = Some(value),
+            "HI" => info.hints = parse_hints(&value).ok_or(GgfError::Syntax(format!("bad hint count HI[{}]", value)))?,
+            "TY" if value.trim().trim_end_matches(char::is_alphabetic) != BOARD_SIZE.to_string() => {
+                return Err(GgfError::UnsupportedBoard(value));
+            }
+            "BO" => game = Some(parse_start_position(&value)?),
+            "B" | "W" => pending_moves.push((tag == "W", value)),
+            // GM[Othello] and tags this program has no use for
+            _ => (),
+        }
+    }
+
+    let mut game = game.ok_or_else(|| GgfError::Syntax("missing start position BO[...]".to_string()))?;
+    // whether the last move was followed by a pass that has not been read yet
+    let mut pass_pending = false;
+    for (index, (white, value)) in pending_moves.into_iter().enumerate() {
+        let move_number = index + 1;
+        let ggf_move = parse_move(&value).ok_or(GgfError::InvalidMove { move_number, text: value.clone() })?;
+        match &ggf_move.square {
+            None => {
+                if !pass_pending || white == game.board_essential.white_turn {
+                    return Err(GgfError::IllegalMove { move_number, text: value });
+                }
+                pass_pending = false;
+            }
+            Some(square) => {
+                // a pass that is not written out is accepted as well
+                if white != game.board_essential.white_turn {
+                    return Err(GgfError::WrongColour { move_number });
+                }
+                if game.board_essential.game_over || !legal_moves(&game.board_essential).contains(square) {
+                    return Err(GgfError::IllegalMove { move_number, text: value });
+                }
+                do_move(square.x, square.y, &mut game);
+                pass_pending = !game.board_essential.game_over && game.board_essential.white_turn == white;
+            }
+        }
+        moves.push(ggf_move);
+    }
+    if let Some(variations) = &info.variations {
+        let (board, white_turn) = start_position(&game);
+        parse_tree(variations, &board_essentials_from_board(board, white_turn))
+            .map_err(|e| GgfError::Syntax(e.to_string()))?;
+    }
+    game.info = info;
+    Ok(GgfRecord { game, moves })
+}
+
+fn parse_hints(value: &str) -> Option<[usize; 2]> {
+    let mut counts = value.split_whitespace().map(|count| count.parse().ok());
+    let hints = [counts.next()??, counts.next()??];
+    counts.next().is_none().then_some(hints)
+}
+
+fn parse_start_position(value: &str) -> Result<Game, GgfError> {
+    let invalid = || GgfError::InvalidPosition(value.to_string());
+    let mut parts = value.split_whitespace();
+    let size = parts.next().ok_or_else(invalid)?;
+    if size != BOARD_SIZE.to_string() {
+        return Err(GgfError::UnsupportedBoard(size.to_string()));
+    }
+    let squares: Vec<char> = parts.collect::<String>().chars().collect();
+    if squares.len() != BOARD_SIZE * BOARD_SIZE + 1 {
+        return Err(invalid());
+    }
+    let mut board = [[Colour::EMPTY; BOARD_SIZE]; BOARD_SIZE];
+    for (i, c) in squares[..BOARD_SIZE * BOARD_SIZE].iter().enumerate() {
+        board[i % BOARD_SIZE][i / BOARD_SIZE] = match c {
+            '*' => Colour::BLACK,
+            'O' => Colour::WHITE,
+            '-' => Colour::EMPTY,
+            _ => return Err(invalid()),
+        };
+    }
+    let white_turn = match squares[BOARD_SIZE * BOARD_SIZE] {
+        '*' => false,
+        'O' => true,
+        _ => return Err(invalid()),
+    };
+    Ok(game_from_board_essentials(board_essentials_from_board(board, white_turn)))
+}
+
+fn parse_move(value: &str) -> Option<GgfMove> {
+    let mut parts = value.split('/');
+    let square_text = parts.next()?.trim();
+    let square = if square_text.eq_ignore_ascii_case("pa") || square_text.eq_ignore_ascii_case("pass") {
+        None
+    } else {
+        Some(square_text.parse().ok()?)
+    };
+    let mut number = || match parts.next().map(str::trim) {
+        None | Some("") => Some(None),
+        Some(text) => text.parse::<f64>().ok().map(Some),
+    };
+    Some(GgfMove { square, eval: number()?, time: number()? })
+}
+
+pub fn write_ggf(record: &GgfRecord) -> String {
+    let info = &record.game.info;
+    let mut text = String::from("(;GM[Othello]");
+    let mut tag = |name: &str, value: &Option<String>| {
+        if let Some(value) = value {
+            text.push_str(&format!("{}[{}]", name, value));
+        }
+    };
+    tag("PC", &info.place);
+    tag("DT", &info.date);
+    tag("PB", &info.black_player);
+    tag("PW", &info.white_player);
+    tag("RB", &info.black_rating.map(|r| format!("{:.2}", r)));
+    tag("RW", &info.white_rating.map(|r| format!("{:.2}", r)));
+    tag("TI", &info.time_control);
+    tag("TY", &Some(BOARD_SIZE.to_string()));
+    tag("RE", &info.result);
+    tag("VA", &info.variations);
+    tag("HI", &(info.hints != [0, 0]).then(|| format!("{} {}", info.hints[0], info.hints[1])));
+
+    let (board, white_turn) = start_position(&record.game);
+    text.push_str(&format!("BO[{} ", BOARD_SIZE));
+    for y in 0..BOARD_SIZE {
+        for column in board.iter() {
+            text.push(match column[y] {
+                Colour::BLACK => '*',
+                Colour::WHITE => 'O',
+                Colour::EMPTY => '-',
+            });
+        }
+    }
+    text.push_str(if white_turn { " O]" } else { " *]" });
+
+    let mut white = white_turn;
+    for ggf_move in record.moves.iter() {
+        text.push_str(if white { "W[" } else { "B[" });
+        match &ggf_move.square {
+            Some(square) => text.push_str(&square.to_string().to_uppercase()),
+            None => text.push_str("PA"),
+        }
+        if ggf_move.eval.is_some() || ggf_move.time.is_some() {
+            text.push('/');
+            if let Some(eval) = ggf_move.eval {
+                text.push_str(&format!("{:.2}", eval));
+            }
+            if let Some(time) = ggf_move.time {
+                text.push_str(&format!("/{:.2}", time));
+            }
+        }
+        text.push(']');
+        white = !white;
+    }
+    text.push_str(";)");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{game_from_transcript, transcript};
+
+    const EXAMPLE: &str = "(;GM[Othello]PC[NOS]DT[2003.12.15_13:24:03.MST]PB[alice]PW[bob]RB[1850.20]RW[1790.40]\
+        TI[15:00//02:00]TY[8]RE[+18.000]\
+        BO[8 ---------------------------O*------*O--------------------------- *]\
+        B[F5//0.02]W[D6/-1.50/1.10]B[C3]W[D3/0.5];)";
+
+    #[test]
+    fn reads_players_position_and_moves() {
+        let records = parse_ggf(EXAMPLE).unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        let info = &record.game.info;
+        assert_eq!(info.black_player.as_deref(), Some("alice"));
+        assert_eq!(info.white_rating, Some(1790.4));
+        assert_eq!(info.time_control.as_deref(), Some("15:00//02:00"));
+        assert_eq!(info.result.as_deref(), Some("+18.000"));
+        assert_eq!(transcript(&record.game), "f5d6c3d3");
+        assert_eq!(record.moves[0].time, Some(0.02));
+        assert_eq!(record.moves[1].eval, Some(-1.5));
+        assert_eq!(record.moves[3].time, None);
+        // standard start: black to move after white's d3
+        assert!(!record.game.board_essential.white_turn);
+    }
+
+    #[test]
+    fn write_then_read_gives_the_same_game() {
+        let record = &parse_ggf(EXAMPLE).unwrap()[0];
+        let reread = &parse_ggf(&write_ggf(record)).unwrap()[0];
+        assert_eq!(reread.game.info, record.game.info);
+        assert_eq!(reread.moves, record.moves);
+        assert_eq!(reread.game.board_essential.board, record.game.board_essential.board);
+
+        let mut game = game_from_transcript("c4c3c2b4a5f4g4c5d6").unwrap();
+        game.info.hints = [2, 0];
+        let text = write_ggf(&GgfRecord::from_game(&game));
+        assert!(text.contains("HI[2 0]"));
+        let reread = &parse_ggf(&text).unwrap()[0];
+        assert_eq!(transcript(&reread.game), "c4c3c2b4a5f4g4c5d6");
+        assert_eq!(reread.game.info.hints, [2, 0]);
+        assert!(reread.game.board_essential.game_over);
+    }
+
+    #[test]
+    fn rejects_bad_records() {
+        assert!(matches!(parse_ggf("GM[Othello]"), Err(GgfError::Syntax(_))));
+        assert!(matches!(parse_ggf(&EXAMPLE.replace("TY[8]", "TY[8]HI[1]")), Err(GgfError::Syntax(_))));
+        assert!(matches!(
+            parse_ggf(&EXAMPLE.replace("TY[8]", "TY[10]")),
+            Err(GgfError::UnsupportedBoard(_))
+        ));
+        assert_eq!(
+            parse_ggf(&EXAMPLE.replace("B[C3]", "B[A1]")).err(),
+            Some(GgfError::IllegalMove { move_number: 3, text: "A1".to_string() })
+        );
+        assert_eq!(
+            parse_ggf(&EXAMPLE.replace("B[C3]W[D3/0.5]", "W[C3]")).err(),
+            Some(GgfError::WrongColour { move_number: 3 })
+        );
+    }
+}
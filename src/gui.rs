@@ -0,0 +1,1466 @@
+use crate::analysis::{Analysis, Evaluator};
+use crate::controller::{Controller, Player};
+use crate::savegame::{autosaved_game, load_game, players_from_info, save_game, Autosave};
+use crate::settings::{settings, ThemeColours};
+use crate::{
+    board_essentials_from_board, count_stones, game_from_board_essentials, get_all_possible_moves, ggf, legal_moves,
+    notation, print_game_information, set_up_board, start_position, BoardEssentials, Colour, Game, Square, BOARD_SIZE,
+};
+use piston_window::graphics::character::CharacterCache;
+use piston_window::graphics::text::Text;
+use piston_window::graphics::{clear, ellipse, line, rectangle, Context, Ellipse, Transformed};
+use piston_window::wgpu_graphics::{TextureSettings, WgpuGraphics};
+use piston_window::*;
+
+// The window. It opens on a start screen to choose the players, who moves first and the starting
+// position; while playing, Escape opens a menu to go back to the game, start a new one, change the
+// players or resign. The menus are used with the arrow keys and enter, or by clicking on a line.
+// In the game U or left takes a move back, R or right replays it, Home and End go to the start and
+// the end of the moves played. Playing another move after going back starts a variation: up and
+// down switch between the variations of the last move, M makes the current one the main line and
+// Delete removes it. S saves the game to the save_file setting and L loads it again. H shows the
+// engine's best move for the human to move, pressing it again the best three with their values;
+// the hints taken are counted in the game's record. A switches the analysis overlay on and off,
+// which scores every legal move on its square, from green for the best to red for the worst,
+// searching deeper while the position stays on the board.
+// The game in progress is autosaved after every move; if one was left unfinished, the start screen
+// offers to resume it.
+// The board has its coordinates, a-h and 1-8, around it. Pointing at a legal move of the human to
+// move shows the disc it would place and marks the discs it would flip.
+// Beside the board a panel shows the disc counts and mobility of both sides, whose turn it is and
+// passes; the result is shown over the board when the game is over. Right of it the move list
+// shows the moves to the end of the line played, with the current one highlighted; clicking a move
+// goes to the position after it, clicking the colours at the top goes to the start.
+// At the bottom of the panel a bar shows who is ahead in the position on the board according to
+// the engine, and a graph its evaluation over the moves of the line, up for white and down for
+// black; clicking on the graph goes to that move.
+
+// AI levels are search depths.
+const MAX_LEVEL: usize = 10;
+// The margin around the board for its coordinates, as a fraction of the size of board and margins.
+const COORDINATE_MARGIN: f64 = 0.05;
+// How much deeper than the AI the analysis overlay searches.
+const EXTRA_ANALYSIS_DEPTH: usize = 3;
+// The evaluation where the bar is full and the graph at the top (or empty and at the bottom).
+const EVALUATION_RANGE: isize = 40;
+// The panel right of the board, as a fraction of the board's size.
+const PANEL_WIDTH: f64 = 0.45;
+// The move list right of the panel, in the same way.
+const MOVE_LIST_WIDTH: f64 = 0.35;
+// The width of the window for a board of size 1.
+const WINDOW_WIDTH: f64 = 1.0 + PANEL_WIDTH + MOVE_LIST_WIDTH;
+
+// The arrangement of the four discs in the centre.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variant {
+    // the side moving second on d4 and e5: white when black moves first, as in standard Othello
+    Standard,
+    // the side moving first on d4 and e5
+    Swapped,
+    // black on d4 and e4, white on d5 and e5
+    Parallel,
+}
+
+impl Variant {
+    fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Swapped => "swapped colours",
+            Variant::Parallel => "parallel",
+        }
+    }
+
+    fn next(&self, forward: bool) -> Variant {
+        match (self, forward) {
+            (Variant::Standard, true) | (Variant::Swapped, false) => Variant::Parallel,
+            (Variant::Swapped, true) | (Variant::Parallel, false) => Variant::Standard,
+            (Variant::Parallel, true) | (Variant::Standard, false) => Variant::Swapped,
+        }
+    }
+
+    fn board(&self, white_first: bool) -> [[Colour; BOARD_SIZE]; BOARD_SIZE] {
+        let mut board = set_up_board(white_first);
+        let (low, high) = (BOARD_SIZE / 2 - 1, BOARD_SIZE / 2);
+        match self {
+            Variant::Standard => (),
+            Variant::Swapped => {
+                for x in [low, high] {
+                    for y in [low, high] {
+                        board[x][y] = if board[x][y] == Colour::BLACK { Colour::WHITE } else { Colour::BLACK };
+                    }
+                }
+            }
+            Variant::Parallel => {
+                for x in [low, high] {
+                    board[x][low] = Colour::BLACK;
+                    board[x][high] = Colour::WHITE;
+                }
+            }
+        }
+        board
+    }
+}
+
+// A side as chosen on the start screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Human,
+    // the level is the search depth
+    Ai(usize),
+}
+
+impl Side {
+    fn next(&self, forward: bool) -> Side {
+        match (self, forward) {
+            (Side::Human, true) => Side::Ai(1),
+            (Side::Human, false) => Side::Ai(MAX_LEVEL),
+            (Side::Ai(level), true) if *level >= MAX_LEVEL => Side::Human,
+            (Side::Ai(level), false) if *level <= 1 => Side::Human,
+            (Side::Ai(level), true) => Side::Ai(level + 1),
+            (Side::Ai(level), false) => Side::Ai(level - 1),
+        }
+    }
+}
+
+struct Options {
+    // black then white
+    sides: [Side; 2],
+    white_first: bool,
+    variant: Variant,
+}
+
+impl Options {
+    fn from_settings() -> Options {
+        let side = |player| match player {
+            Player::Human => Side::Human,
+            Player::Ai => Side::Ai(settings().depth.min(MAX_LEVEL)),
+        };
+        Options {
+            sides: settings().players.map(side),
+            white_first: settings().white_is_starting,
+            variant: Variant::Standard,
+        }
+    }
+
+    // Gives the controller these players.
+    fn apply_sides(&self, controller: &mut Controller) {
+        for (i, side) in self.sides.iter().enumerate() {
+            match side {
+                Side::Human => controller.players[i] = Player::Human,
+                Side::Ai(level) => {
+                    controller.players[i] = Player::Ai;
+                    controller.ai_depths[i] = *level;
+                }
+            }
+        }
+    }
+
+    // Takes the players from a game saved here.
+    fn sides_from_game(&mut self, game: &Game) {
+        if let Some(players) = players_from_info(&game.info) {
+            self.sides = players.map(|(player, depth)| match player {
+                Player::Human => Side::Human,
+                Player::Ai => Side::Ai(depth),
+            });
+        }
+    }
+
+    fn new_game(&self) -> Controller {
+        let board_essential = board_essentials_from_board(self.variant.board(self.white_first), self.white_first);
+        let mut controller =
+            Controller::new(game_from_board_essentials(board_essential), settings().players, settings().depth);
+        self.apply_sides(&mut controller);
+        controller
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Screen {
+    Start,
+    Playing,
+    Menu,
+    // changing the players during a game
+    Players,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Item {
+    // false for black, true for white
+    Side(bool),
+    FirstMove,
+    Variant,
+    Start,
+    // the game left unfinished last time
+    Recover,
+    Resume,
+    NewGame,
+    ChangePlayers,
+    Resign,
+    Quit,
+}
+
+fn items(screen: Screen) -> &'static [Item] {
+    match screen {
+        Screen::Start => &[Item::Side(false), Item::Side(true), Item::FirstMove, Item::Variant, Item::Start, Item::Quit],
+        Screen::Playing => &[],
+        Screen::Menu => &[Item::Resume, Item::NewGame, Item::ChangePlayers, Item::Resign, Item::Quit],
+        Screen::Players => &[Item::Side(false), Item::Side(true), Item::Resume],
+    }
+}
+
+fn colour_name(white: bool) -> &'static str {
+    if white {
+        "White"
+    } else {
+        "Black"
+    }
+}
+
+// The moves suggested for a position, shown while it is on the board.
+struct Hint {
+    board: [[Colour; BOARD_SIZE]; BOARD_SIZE],
+    white_turn: bool,
+    // best first, with their values from white's point of view
+    moves: Vec<(Square, isize)>,
+    // all of them rather than only the best
+    all: bool,
+}
+
+// A line of moves as the position it starts from and the moves.
+type Line = (([[Colour; BOARD_SIZE]; BOARD_SIZE], bool), Vec<Square>);
+
+struct Gui {
+    controller: Controller,
+    options: Options,
+    screen: Screen,
+    // the highlighted line of the menu
+    selected: usize,
+    // the outcome of the last save or load
+    message: String,
+    hint: Option<Hint>,
+    // whether the analysis overlay is on, and the search for it
+    analysing: bool,
+    analysis: Option<Analysis>,
+    evaluator: Evaluator,
+    // the line the evaluations are for, and the positions along it
+    evaluated_line: Option<Line>,
+    line_positions: Vec<BoardEssentials>,
+    // the evaluations of the positions along the line, from the start to its end
+    evaluations: Vec<Option<isize>>,
+    // offered on the start screen
+    autosaved: Option<Game>,
+    quit: bool,
+}
+
+impl Gui {
+    fn new(game: Option<Game>) -> Gui {
+        let options = Options::from_settings();
+        let controller = options.new_game();
+        let mut gui = Gui {
+            controller,
+            options,
+            screen: Screen::Start,
+            selected: 0,
+            message: String::new(),
+            hint: None,
+            analysing: false,
+            analysis: None,
+            evaluator: Evaluator::new(settings().depth),
+            evaluated_line: None,
+            line_positions: vec![],
+            evaluations: vec![],
+            autosaved: None,
+            quit: false,
+        };
+        // a game given on the command line is played straight away
+        if let Some(game) = game {
+            gui.resume(game);
+        }
+        gui
+    }
+
+    // Continues `game`, with its players if it was saved here.
+    fn resume(&mut self, game: Game) {
+        self.options.sides_from_game(&game);
+        self.controller = Controller::new(game, settings().players, settings().depth);
+        self.options.apply_sides(&mut self.controller);
+        self.open(Screen::Playing);
+    }
+
+    fn save(&mut self) {
+        let path = &settings().save_file;
+        self.message = match save_game(&self.controller, path) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(error) => error,
+        };
+        println!("{}", self.message);
+    }
+
+    fn load(&mut self) {
+        let path = &settings().save_file;
+        match load_game(path) {
+            Ok(game) => {
+                self.resume(game);
+                self.message = format!("Loaded {}", path.display());
+                print_game_information(&self.controller.game);
+            }
+            Err(error) => self.message = error,
+        }
+        println!("{}", self.message);
+    }
+
+    // The first time a human asks for a hint in a position the best move is shown, the next time
+    // the best three.
+    fn ask_hint(&mut self) {
+        if !self.controller.human_to_move() {
+            return;
+        }
+        if let Some(hint) = &mut self.hint {
+            if hint.board == self.controller.game.board_essential.board
+                && hint.white_turn == self.controller.game.board_essential.white_turn
+            {
+                hint.all = true;
+                return;
+            }
+        }
+        let moves = self.controller.take_hint(3);
+        let board_essential = &self.controller.game.board_essential;
+        self.hint = Some(Hint { board: board_essential.board, white_turn: board_essential.white_turn, moves, all: false });
+    }
+
+    // The suggested moves to show for the position on the board.
+    fn hint_moves(&self) -> &[(Square, isize)] {
+        let board_essential = &self.controller.game.board_essential;
+        match &self.hint {
+            Some(hint) if hint.board == board_essential.board && hint.white_turn == board_essential.white_turn => {
+                &hint.moves[..if hint.all { hint.moves.len() } else { hint.moves.len().min(1) }]
+            }
+            _ => &[],
+        }
+    }
+
+    // Keeps the analysis overlay's search on the position on the board, while playing.
+    fn update_analysis(&mut self) {
+        let board_essential = &self.controller.game.board_essential;
+        if !self.analysing || self.screen != Screen::Playing || board_essential.game_over {
+            self.analysis = None;
+            return;
+        }
+        if !self.analysis.as_ref().is_some_and(|analysis| analysis.is_for(board_essential)) {
+            self.analysis = Some(Analysis::start(board_essential, self.controller.depth + EXTRA_ANALYSIS_DEPTH));
+        }
+        if let Some(analysis) = &mut self.analysis {
+            analysis.poll();
+        }
+    }
+
+    // Asks for the evaluations of the positions along the line when it changes, and takes in those
+    // found.
+    fn update_evaluations(&mut self) {
+        let line = (start_position(&self.controller.game), self.controller.line_moves());
+        let changed = self.evaluated_line.as_ref() != Some(&line);
+        if changed {
+            self.line_positions = line_positions(&self.controller);
+            for position in &self.line_positions {
+                self.evaluator.request(position);
+            }
+            self.evaluated_line = Some(line);
+        }
+        if self.evaluator.poll() || changed {
+            self.evaluations = self.line_positions.iter().map(|position| self.evaluator.value(position)).collect();
+        }
+    }
+
+    // The lines of the menu on screen.
+    fn menu_items(&self) -> Vec<Item> {
+        let mut menu_items = items(self.screen).to_vec();
+        if self.screen == Screen::Start && self.autosaved.is_some() {
+            menu_items.insert(0, Item::Recover);
+        }
+        menu_items
+    }
+
+    fn open(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.selected = 0;
+    }
+
+    // The side that resigns: the human to move, or else the human playing against the AI.
+    fn resigning_side(&self) -> Option<bool> {
+        if self.controller.game.board_essential.game_over {
+            return None;
+        }
+        if self.controller.human_to_move() {
+            return Some(self.controller.game.board_essential.white_turn);
+        }
+        self.controller.players.iter().position(|player| *player == Player::Human).map(|i| i == 1)
+    }
+
+    fn label(&self, item: Item) -> String {
+        match item {
+            Item::Side(white) => match self.options.sides[white as usize] {
+                Side::Human => format!("{}: human", colour_name(white)),
+                Side::Ai(level) => format!("{}: AI level {}", colour_name(white), level),
+            },
+            Item::FirstMove => format!("First move: {}", colour_name(self.options.white_first).to_lowercase()),
+            Item::Variant => format!("Start position: {}", self.options.variant.name()),
+            Item::Start => "Start game".to_string(),
+            Item::Recover => match &self.autosaved {
+                Some(game) => format!("Resume unfinished game ({} moves)", game.moves.len()),
+                None => "Resume unfinished game".to_string(),
+            },
+            Item::Resume => "Back to the game".to_string(),
+            Item::NewGame => "New game".to_string(),
+            Item::ChangePlayers => "Change players".to_string(),
+            Item::Resign => match self.resigning_side() {
+                Some(white) => format!("Resign ({})", colour_name(white).to_lowercase()),
+                None => "Resign".to_string(),
+            },
+            Item::Quit => "Quit".to_string(),
+        }
+    }
+
+    // Carries out a menu line: an option changes to its next (or previous) value, anything else
+    // happens when going forward.
+    fn activate(&mut self, item: Item, forward: bool) {
+        match item {
+            Item::Side(white) => {
+                let side = &mut self.options.sides[white as usize];
+                *side = side.next(forward);
+                if self.screen == Screen::Players {
+                    self.options.apply_sides(&mut self.controller);
+                }
+            }
+            Item::FirstMove => self.options.white_first = !self.options.white_first,
+            Item::Variant => self.options.variant = self.options.variant.next(forward),
+            _ if !forward => (),
+            Item::Start => {
+                self.controller = self.options.new_game();
+                self.open(Screen::Playing);
+                print_game_information(&self.controller.game);
+            }
+            Item::Recover => {
+                if let Some(game) = self.autosaved.take() {
+                    self.resume(game);
+                    print_game_information(&self.controller.game);
+                }
+            }
+            Item::Resume => self.open(Screen::Playing),
+            Item::NewGame => self.open(Screen::Start),
+            Item::ChangePlayers => self.open(Screen::Players),
+            Item::Resign => {
+                if let Some(white) = self.resigning_side() {
+                    self.controller.resign(white);
+                    println!("{} resigns.", colour_name(white));
+                    print_game_information(&self.controller.game);
+                    self.open(Screen::Playing);
+                }
+            }
+            Item::Quit => self.quit = true,
+        }
+    }
+
+    fn handle_key(&mut self, key: Key) {
+        if self.screen == Screen::Playing {
+            match key {
+                Key::Escape => self.open(Screen::Menu),
+                // against the AI its replies are taken back as well
+                Key::U | Key::Left => {
+                    self.controller.undo_to_human();
+                }
+                Key::R | Key::Right => {
+                    self.controller.redo_to_human();
+                }
+                Key::Home => self.controller.go_to(0),
+                Key::End => self.controller.go_to(self.controller.history_len()),
+                Key::T => println!("Transcript: {}", notation::transcript(&self.controller.game)),
+                Key::P => println!("Position: {}", self.controller.game.board_essential),
+                Key::G => println!("{}", ggf::write_ggf(&ggf::GgfRecord::from_game(&self.controller.record()))),
+                Key::Up => {
+                    self.controller.switch_variation(false);
+                }
+                Key::Down => {
+                    self.controller.switch_variation(true);
+                }
+                Key::H => self.ask_hint(),
+                Key::A => {
+                    self.analysing = !self.analysing;
+                    self.update_analysis();
+                }
+                Key::S => self.save(),
+                Key::L => self.load(),
+                Key::M => {
+                    self.controller.promote_variation();
+                }
+                Key::Delete => {
+                    self.controller.delete_variation();
+                }
+                _ => (),
+            }
+            return;
+        }
+        let items = self.menu_items();
+        match key {
+            Key::Up => self.selected = (self.selected + items.len() - 1) % items.len(),
+            Key::Down => self.selected = (self.selected + 1) % items.len(),
+            Key::Left => self.activate(items[self.selected], false),
+            Key::Right | Key::Return | Key::Space => self.activate(items[self.selected], true),
+            // Escape leaves the menu, except on the start screen where there is no game to go back to
+            Key::Escape if self.screen != Screen::Start => self.open(Screen::Playing),
+            _ => (),
+        }
+    }
+
+    // A click on the board plays there and one in the move list goes to the move clicked on; in a
+    // menu it carries out the line clicked on.
+    fn click(&mut self, mouse: [f64; 2], size: f64) {
+        if self.screen == Screen::Playing {
+            if let Some(ply) = move_at(&move_slots(&self.controller), self.controller.game.moves.len(), mouse, size) {
+                self.controller.go_to(ply);
+                return;
+            }
+            if let Some(ply) = graph_ply_at(mouse, size, self.evaluations.len()) {
+                self.controller.go_to(ply);
+                return;
+            }
+            if let Some(square) = square_at(mouse, size) {
+                if self.controller.human_to_move() && self.controller.play(square).is_ok() {
+                    print_game_information(&self.controller.game);
+                }
+            }
+            return;
+        }
+        let items = self.menu_items();
+        if let Some(line) = menu_line_at(mouse[1], size, items.len()) {
+            self.selected = line;
+            self.activate(items[line], true);
+        }
+    }
+}
+
+// The number of legal moves `white` (or black) would have in the position.
+fn mobility(board_essential: &BoardEssentials, white: bool) -> usize {
+    if board_essential.white_turn == white {
+        return legal_moves(board_essential).len();
+    }
+    let mut other_side = board_essential.clone();
+    other_side.white_turn = white;
+    other_side.possible_moves = get_all_possible_moves(&other_side).0;
+    legal_moves(&other_side).len()
+}
+
+// The side that had to pass before the current turn, if any.
+fn passed(game: &Game) -> Option<bool> {
+    match game.prev_boards.last() {
+        Some((_, white_moved)) if !game.board_essential.game_over && *white_moved == game.board_essential.white_turn => {
+            Some(!white_moved)
+        }
+        _ => None,
+    }
+}
+
+// The two lines of the panel about one side, e.g. "Black 12" and "AI level 5, 7 moves".
+fn side_lines(controller: &Controller, white: bool) -> [String; 2] {
+    let (black, white_discs) = count_stones(&controller.game.board_essential.board);
+    let discs = if white { white_discs } else { black };
+    let player = match controller.players[white as usize] {
+        Player::Human => "human".to_string(),
+        Player::Ai => format!("AI level {}", controller.ai_depths[white as usize]),
+    };
+    let moves = mobility(&controller.game.board_essential, white);
+    [format!("{} {}", colour_name(white), discs), format!("{}, {} move{}", player, moves, if moves == 1 { "" } else { "s" })]
+}
+
+// A move's value for the side to move, e.g. "+4" or "win".
+fn move_value(value: isize, white: bool) -> String {
+    match (value, white) {
+        (isize::MAX, true) | (isize::MIN, false) => "win".to_string(),
+        (isize::MIN, true) | (isize::MAX, false) => "loss".to_string(),
+        (value, true) => format!("{:+}", value),
+        (value, false) => format!("{:+}", -value),
+    }
+}
+
+// The panel line about the hint shown, e.g. "Hint: c4 +3, e6 +1".
+fn hint_line(moves: &[(Square, isize)], white: bool) -> Option<String> {
+    if moves.is_empty() {
+        return None;
+    }
+    let moves: Vec<String> = moves.iter().map(|(square, value)| format!("{} {}", square, move_value(*value, white))).collect();
+    Some(format!("Hint: {}", moves.join(", ")))
+}
+
+// The panel line about the analysis overlay, e.g. "Analysis: depth 6".
+fn analysis_line(gui: &Gui) -> Option<String> {
+    let analysis = gui.analysis.as_ref()?;
+    Some(match analysis.scores() {
+        Some(scores) => format!("Analysis: depth {}", scores.depth),
+        None => "Analysis: searching...".to_string(),
+    })
+}
+
+// The colour of the move ranked `rank` of `count` on the analysis overlay, from green for the best
+// to red for the worst.
+fn rank_colour(rank: usize, count: usize) -> [f32; 4] {
+    let worse = if count > 1 { rank as f32 / (count - 1) as f32 } else { 0.0 };
+    [0.1 + 0.8 * worse, 0.8 - 0.7 * worse, 0.1, 0.6]
+}
+
+// The positions along the current line to its end: the start and the position after each move.
+fn line_positions(controller: &Controller) -> Vec<BoardEssentials> {
+    let game = controller.record();
+    let mut positions: Vec<BoardEssentials> =
+        game.prev_boards.iter().map(|(board, white_turn)| board_essentials_from_board(*board, *white_turn)).collect();
+    positions.push(game.board_essential);
+    positions
+}
+
+// The part of the evaluation bar that is white, from 0 when black is well ahead to 1 for white.
+fn white_share(value: isize) -> f64 {
+    0.5 + value.clamp(-EVALUATION_RANGE, EVALUATION_RANGE) as f64 / (2 * EVALUATION_RANGE) as f64
+}
+
+// The evaluation bar and graph share the bottom of the panel, under the lines of text.
+fn evaluation_bar_area(size: f64) -> [f64; 4] {
+    [size * 1.04, size * 0.76, size * 0.03, size * 0.2]
+}
+
+fn evaluation_graph_area(size: f64) -> [f64; 4] {
+    [size * 1.1, size * 0.76, size * (PANEL_WIDTH - 0.12), size * 0.2]
+}
+
+// Where the graph has the position after `ply` of `plies` moves (or the start).
+fn graph_x(ply: usize, plies: usize, size: f64) -> f64 {
+    let [x, _, width, _] = evaluation_graph_area(size);
+    if plies == 0 {
+        x
+    } else {
+        x + width * ply as f64 / plies as f64
+    }
+}
+
+// The position nearest to a click on the graph of `positions` positions.
+fn graph_ply_at([x, y]: [f64; 2], size: f64, positions: usize) -> Option<usize> {
+    let [left, top, width, height] = evaluation_graph_area(size);
+    if positions == 0 || x < left || x > left + width || y < top || y > top + height {
+        return None;
+    }
+    let plies = positions - 1;
+    Some(((x - left) / width * plies as f64).round() as usize)
+}
+
+// How many hints each side took in the game, once there are any.
+fn hints_taken(game: &Game) -> Option<String> {
+    let [black, white] = game.info.hints;
+    (black + white > 0).then(|| format!("Hints taken: black {}, white {}", black, white))
+}
+
+// Whose turn it is and any pass, under the sides in the panel.
+fn status_lines(controller: &Controller) -> Vec<String> {
+    let game = &controller.game;
+    let mut lines = vec![];
+    if game.board_essential.game_over {
+        lines.push("Game over".to_string());
+        return lines;
+    }
+    let white = game.board_essential.white_turn;
+    if let Some((number, count)) = controller.variation() {
+        lines.push(format!("Variation {} of {}", number, count));
+    }
+    if let Some(passer) = passed(game) {
+        lines.push(format!("{} had to pass", colour_name(passer)));
+    }
+    lines.push(match controller.player_to_move() {
+        Player::Human => format!("{} to move", colour_name(white)),
+        Player::Ai => format!("{} is thinking...", colour_name(white)),
+    });
+    lines
+}
+
+// The banner over the board once the game is over, e.g. "Black wins 40 - 24".
+fn result_banner(game: &Game) -> Option<String> {
+    if !game.board_essential.game_over {
+        return None;
+    }
+    let (black, white) = count_stones(&game.board_essential.board);
+    let resigned = game.info.result.as_deref().is_some_and(|result| result.ends_with(":r"));
+    Some(match game.board_essential.winner {
+        Colour::BLACK if resigned => "Black wins, white resigned".to_string(),
+        Colour::WHITE if resigned => "White wins, black resigned".to_string(),
+        Colour::BLACK => format!("Black wins {} - {}", black, white),
+        Colour::WHITE => format!("White wins {} - {}", white, black),
+        Colour::EMPTY => format!("Draw {} - {}", black, white),
+    })
+}
+
+// A line of the move list holds the moves of both sides, with a pass where a side could not move.
+#[derive(Debug, PartialEq)]
+struct Slot {
+    // the number of moves played once this one is on the board, None for a pass
+    ply: Option<usize>,
+    text: String,
+}
+
+// The moves to the end of the current line, those of the side that moved first in the even slots.
+fn move_slots(controller: &Controller) -> Vec<Slot> {
+    let game = controller.record();
+    let (_, mut white) = start_position(&game);
+    let mut slots = vec![];
+    for (i, (square, (_, white_moved))) in game.moves.iter().zip(&game.prev_boards).enumerate() {
+        if *white_moved != white {
+            slots.push(Slot { ply: None, text: "pass".to_string() });
+        }
+        slots.push(Slot { ply: Some(i + 1), text: square.to_string() });
+        white = !white_moved;
+    }
+    slots
+}
+
+// The move list has a line for the colours at the top and then one per move number, each a 28th of
+// the board high.
+fn move_row_height(size: f64) -> f64 {
+    size / 28.0
+}
+
+fn move_list_left(size: f64) -> f64 {
+    size * (1.0 + PANEL_WIDTH)
+}
+
+// The move lines that fit under the colours.
+fn visible_move_rows(size: f64) -> usize {
+    (size * 0.96 / move_row_height(size)) as usize - 1
+}
+
+// The first move line shown: the list scrolls to keep the current move in view.
+fn first_move_row(slots: &[Slot], ply: usize, visible: usize) -> usize {
+    let current_row = slots.iter().position(|slot| slot.ply == Some(ply)).map_or(0, |i| i / 2);
+    (current_row + 1).saturating_sub(visible)
+}
+
+// Where the columns of the move list start: the move number, the first and the second mover.
+fn move_columns(size: f64) -> [f64; 3] {
+    let left = move_list_left(size);
+    [left + size * 0.02, left + size * 0.11, left + size * 0.22]
+}
+
+// The position to go to for a click in the move list at `mouse`: a move (0 for the colours at the
+// top, which stand for the start).
+fn move_at(slots: &[Slot], ply: usize, [x, y]: [f64; 2], size: f64) -> Option<usize> {
+    let columns = move_columns(size);
+    let row_height = move_row_height(size);
+    let top = size * 0.02;
+    if x < columns[1] || x >= columns[2] + size * 0.11 || y < top {
+        return None;
+    }
+    let row = ((y - top) / row_height) as usize;
+    if row == 0 {
+        return Some(0);
+    }
+    let visible = visible_move_rows(size);
+    if row > visible {
+        return None;
+    }
+    let column = if x < columns[2] { 0 } else { 1 };
+    let index = (first_move_row(slots, ply, visible) + row - 1) * 2 + column;
+    slots.get(index)?.ply
+}
+
+// Where the board starts, right and down from the top left corner, and how big it is: the board is
+// drawn inside the margin for the coordinates.
+fn board_area(size: f64) -> (f64, f64) {
+    let margin = size * COORDINATE_MARGIN;
+    (margin, size - 2.0 * margin)
+}
+
+// The square under `mouse`, if it is on the board.
+fn square_at([x, y]: [f64; 2], size: f64) -> Option<Square> {
+    let (offset, board_size) = board_area(size);
+    let dist_per_block = board_size / BOARD_SIZE as f64;
+    let (x, y) = ((x - offset) / dist_per_block, (y - offset) / dist_per_block);
+    let on_board = |value: f64| (0.0..BOARD_SIZE as f64).contains(&value);
+    (on_board(x) && on_board(y)).then_some(Square { x: x as usize, y: y as usize })
+}
+
+// The discs that a move of the human to move on `square` would flip, if it is legal.
+fn flip_preview<'a>(controller: &'a Controller, square: &Square) -> Option<&'a [Square]> {
+    let board_essential = &controller.game.board_essential;
+    if !controller.human_to_move() || board_essential.game_over {
+        return None;
+    }
+    let flips = &board_essential.possible_moves[square.x][square.y];
+    (!flips.is_empty()).then_some(flips.as_slice())
+}
+
+// Menu lines are a tenth of the window high, starting a quarter of the way down.
+fn menu_line_top(line: usize, size: f64) -> f64 {
+    size * (0.25 + 0.1 * line as f64)
+}
+
+fn menu_line_at(y: f64, size: f64, lines: usize) -> Option<usize> {
+    (0..lines).find(|&line| y >= menu_line_top(line, size) && y < menu_line_top(line + 1, size))
+}
+
+fn draw_board(controller: &Controller, theme: &ThemeColours, size: f64, context: Context, graphics: &mut WgpuGraphics) {
+    let dist_per_block = size / BOARD_SIZE as f64;
+    let square = |x: f64, y: f64, scale: f64| {
+        let offset = (1.0 - scale) / 2.0;
+        [(x + offset) * dist_per_block, (y + offset) * dist_per_block, dist_per_block * scale, dist_per_block * scale]
+    };
+
+    // shading newly flipped and placed stones
+    // only if we have stored a prev board
+    if !controller.game.prev_boards.is_empty() {
+        let Square { x, y } = controller.game.last_placed;
+        rectangle(theme.last_move, square(x as f64, y as f64, 1.0), context.transform, graphics);
+        for sq in controller.game.flipped_tiles_from_move.iter() {
+            rectangle(theme.flipped, square(sq.x as f64, sq.y as f64, 1.0), context.transform, graphics);
+        }
+    }
+
+    for i in 1..(BOARD_SIZE + 1) {
+        let temp = dist_per_block * i as f64;
+        line(theme.grid, 2.0, [0.0, temp, dist_per_block * BOARD_SIZE as f64, temp], context.transform, graphics);
+        line(theme.grid, 2.0, [temp, 0.0, temp, dist_per_block * BOARD_SIZE as f64], context.transform, graphics);
+    }
+    let black = [0.0, 0.0, 0.0, 1.0];
+    let white = [1.0; 4];
+    for (x, column) in controller.game.board_essential.board.iter().enumerate() {
+        for (y, colour) in column.iter().enumerate() {
+            match colour {
+                Colour::BLACK => ellipse(black, square(x as f64, y as f64, 0.6), context.transform, graphics),
+                Colour::WHITE => ellipse(white, square(x as f64, y as f64, 0.6), context.transform, graphics),
+                _ => (),
+            }
+        }
+    }
+    if controller.human_to_move() {
+        for (x, outer_vec) in controller.game.board_essential.possible_moves.iter().enumerate() {
+            for (y, inner_vec) in outer_vec.iter().enumerate() {
+                if !inner_vec.is_empty() {
+                    //this is a valid move
+                    ellipse(theme.legal_move, square(x as f64, y as f64, 0.2), context.transform, graphics)
+                }
+            }
+        }
+    }
+}
+
+// The best move gets a dot in the hint colour; when the best three are shown, each has its rank and
+// value on it.
+fn draw_hints(
+    gui: &Gui,
+    theme: &ThemeColours,
+    size: f64,
+    glyphs: &mut Glyphs,
+    context: Context,
+    graphics: &mut WgpuGraphics,
+) {
+    let dist_per_block = size / BOARD_SIZE as f64;
+    let moves = gui.hint_moves();
+    let white = gui.controller.game.board_essential.white_turn;
+    for (rank, (square, value)) in moves.iter().enumerate() {
+        let [x, y] = [square.x as f64 * dist_per_block, square.y as f64 * dist_per_block];
+        if rank == 0 {
+            let offset = dist_per_block * 0.3;
+            let diameter = dist_per_block * 0.4;
+            ellipse(theme.hint, [x + offset, y + offset, diameter, diameter], context.transform, graphics);
+        }
+        if moves.len() > 1 {
+            let text = format!("{}: {}", rank + 1, move_value(*value, white));
+            let font_size = (dist_per_block / 4.0) as u32;
+            let width = glyphs.width(font_size, &text).unwrap_or(0.0);
+            let position = [x + (dist_per_block - width) / 2.0, y + dist_per_block * 0.95];
+            draw_text(&text, font_size, position, glyphs, context, graphics);
+        }
+    }
+}
+
+fn draw_analysis(gui: &Gui, size: f64, glyphs: &mut Glyphs, context: Context, graphics: &mut WgpuGraphics) {
+    let Some(scores) = gui.analysis.as_ref().and_then(Analysis::scores) else {
+        return;
+    };
+    let dist_per_block = size / BOARD_SIZE as f64;
+    let white = gui.controller.game.board_essential.white_turn;
+    let font_size = (dist_per_block / 3.5) as u32;
+    for (rank, (square, value)) in scores.moves.iter().enumerate() {
+        let [x, y] = [square.x as f64 * dist_per_block, square.y as f64 * dist_per_block];
+        let inset = dist_per_block * 0.08;
+        let tile = [x + inset, y + inset, dist_per_block - 2.0 * inset, dist_per_block - 2.0 * inset];
+        rectangle(rank_colour(rank, scores.moves.len()), tile, context.transform, graphics);
+        let text = move_value(*value, white);
+        let width = glyphs.width(font_size, &text).unwrap_or(0.0);
+        let position = [x + (dist_per_block - width) / 2.0, y + dist_per_block * 0.6];
+        draw_text(&text, font_size, position, glyphs, context, graphics);
+    }
+}
+
+fn draw_evaluation(gui: &Gui, theme: &ThemeColours, size: f64, context: Context, graphics: &mut WgpuGraphics) {
+    let ply = gui.controller.game.moves.len();
+    let [x, y, width, height] = evaluation_bar_area(size);
+    rectangle([0.0, 0.0, 0.0, 1.0], [x, y, width, height], context.transform, graphics);
+    if let Some(value) = gui.evaluations.get(ply).copied().flatten() {
+        let white = height * white_share(value);
+        rectangle([1.0; 4], [x, y, width, white], context.transform, graphics);
+    }
+
+    let [x, y, width, height] = evaluation_graph_area(size);
+    rectangle([0.0, 0.0, 0.0, 0.25], [x, y, width, height], context.transform, graphics);
+    line([1.0, 1.0, 1.0, 0.3], 1.0, [x, y + height / 2.0, x + width, y + height / 2.0], context.transform, graphics);
+    let plies = gui.evaluations.len().saturating_sub(1);
+    let marker = graph_x(ply, plies, size);
+    line(theme.last_move, 2.0, [marker, y, marker, y + height], context.transform, graphics);
+    let point = |i: usize, value: isize| [graph_x(i, plies, size), y + height * (1.0 - white_share(value))];
+    for (i, pair) in gui.evaluations.windows(2).enumerate() {
+        if let [Some(from), Some(to)] = pair {
+            let ([x1, y1], [x2, y2]) = (point(i, *from), point(i + 1, *to));
+            line([1.0; 4], 1.5, [x1, y1, x2, y2], context.transform, graphics);
+        }
+    }
+}
+
+// The letters of the columns above and below the board, the numbers of the rows left and right.
+fn draw_coordinates(size: f64, glyphs: &mut Glyphs, context: Context, graphics: &mut WgpuGraphics) {
+    let (offset, board_size) = board_area(size);
+    let dist_per_block = board_size / BOARD_SIZE as f64;
+    let font_size = (offset * 0.6) as u32;
+    for i in 0..BOARD_SIZE {
+        let middle = offset + (i as f64 + 0.5) * dist_per_block;
+        let letter = ((b'a' + i as u8) as char).to_string();
+        let width = glyphs.width(font_size, &letter).unwrap_or(0.0);
+        for y in [offset * 0.75, size - offset * 0.25] {
+            draw_text(&letter, font_size, [middle - width / 2.0, y], glyphs, context, graphics);
+        }
+        let number = (i + 1).to_string();
+        let width = glyphs.width(font_size, &number).unwrap_or(0.0);
+        for x in [(offset - width) / 2.0, size - (offset + width) / 2.0] {
+            draw_text(&number, font_size, [x, middle + font_size as f64 * 0.35], glyphs, context, graphics);
+        }
+    }
+}
+
+// The disc the move under the mouse would place, half see-through, and a ring on each disc it
+// would flip.
+fn draw_flip_preview(gui: &Gui, mouse: [f64; 2], size: f64, context: Context, graphics: &mut WgpuGraphics) {
+    let Some(square) = square_at(mouse, size) else {
+        return;
+    };
+    let Some(flips) = flip_preview(&gui.controller, &square) else {
+        return;
+    };
+    let (offset, board_size) = board_area(size);
+    let dist_per_block = board_size / BOARD_SIZE as f64;
+    let colour = if gui.controller.game.board_essential.white_turn { [1.0; 4] } else { [0.0, 0.0, 0.0, 1.0] };
+    let disc = |square: &Square, scale: f64| {
+        let start = (1.0 - scale) / 2.0;
+        let [x, y] = [(square.x as f64 + start) * dist_per_block, (square.y as f64 + start) * dist_per_block];
+        [offset + x, offset + y, dist_per_block * scale, dist_per_block * scale]
+    };
+    ellipse([colour[0], colour[1], colour[2], 0.5], disc(&square, 0.6), context.transform, graphics);
+    for flip in flips {
+        Ellipse::new_border(colour, dist_per_block * 0.04).draw(
+            disc(flip, 0.4),
+            &context.draw_state,
+            context.transform,
+            graphics,
+        );
+    }
+}
+
+fn draw_text(
+    text: &str,
+    font_size: u32,
+    [x, y]: [f64; 2],
+    glyphs: &mut Glyphs,
+    context: Context,
+    graphics: &mut WgpuGraphics,
+) {
+    let transform = context.transform.trans(x, y);
+    let _ = Text::new_color([1.0; 4], font_size).draw(text, glyphs, &context.draw_state, transform, graphics);
+}
+
+fn draw_centred(
+    text: &str,
+    font_size: u32,
+    y: f64,
+    size: f64,
+    glyphs: &mut Glyphs,
+    context: Context,
+    graphics: &mut WgpuGraphics,
+) {
+    let width = glyphs.width(font_size, text).unwrap_or(0.0);
+    draw_text(text, font_size, [(size - width) / 2.0, y], glyphs, context, graphics);
+}
+
+fn draw_panel(gui: &Gui, size: f64, glyphs: &mut Glyphs, context: Context, graphics: &mut WgpuGraphics) {
+    let controller = &gui.controller;
+    let left = size + size * 0.04;
+    let line_height = size / 16.0;
+    let font_size = (size / 26.0) as u32;
+    let game = &controller.game;
+    for (row, white) in [false, true].into_iter().enumerate() {
+        let top = size * 0.04 + row as f64 * 3.0 * line_height;
+        // the side to move is marked with a bar
+        if !game.board_essential.game_over && game.board_essential.white_turn == white {
+            rectangle([1.0, 1.0, 1.0, 0.8], [size + 2.0, top, size * 0.015, 2.0 * line_height], context.transform, graphics);
+        }
+        let disc = if white { [1.0; 4] } else { [0.0, 0.0, 0.0, 1.0] };
+        let diameter = line_height * 0.8;
+        ellipse(disc, [left, top + line_height * 0.1, diameter, diameter], context.transform, graphics);
+        let [discs, details] = side_lines(controller, white);
+        let text_left = left + line_height * 1.1;
+        draw_text(&discs, font_size, [text_left, top + line_height * 0.75], glyphs, context, graphics);
+        draw_text(&details, font_size, [text_left, top + line_height * 1.75], glyphs, context, graphics);
+    }
+    let mut lines = status_lines(controller);
+    lines.extend(hint_line(gui.hint_moves(), game.board_essential.white_turn));
+    lines.extend(hints_taken(game));
+    lines.extend(analysis_line(gui));
+    if !gui.message.is_empty() {
+        lines.push(gui.message.clone());
+    }
+    for (row, line) in lines.iter().enumerate() {
+        let y = size * 0.04 + (6.5 + row as f64) * line_height;
+        draw_text(line, font_size, [left, y], glyphs, context, graphics);
+    }
+}
+
+fn draw_move_list(
+    controller: &Controller,
+    size: f64,
+    glyphs: &mut Glyphs,
+    context: Context,
+    graphics: &mut WgpuGraphics,
+) {
+    let left = move_list_left(size);
+    rectangle([0.0, 0.0, 0.0, 0.15], [left, 0.0, size * MOVE_LIST_WIDTH, size], context.transform, graphics);
+    let slots = move_slots(controller);
+    let ply = controller.game.moves.len();
+    let columns = move_columns(size);
+    let row_height = move_row_height(size);
+    let font_size = (size / 32.0) as u32;
+    let baseline = |row: usize| size * 0.02 + (row as f64 + 0.75) * row_height;
+    let highlight = |column: usize, row: usize, graphics: &mut WgpuGraphics| {
+        let [x, y] = [columns[column + 1] - size * 0.01, size * 0.02 + row as f64 * row_height];
+        rectangle([1.0, 1.0, 1.0, 0.25], [x, y, size * 0.1, row_height], context.transform, graphics);
+    };
+
+    let (_, white_first) = start_position(&controller.game);
+    if ply == 0 {
+        highlight(0, 0, graphics);
+        highlight(1, 0, graphics);
+    }
+    for (column, white) in [white_first, !white_first].into_iter().enumerate() {
+        draw_text(colour_name(white), font_size, [columns[column + 1], baseline(0)], glyphs, context, graphics);
+    }
+    let visible = visible_move_rows(size);
+    let first = first_move_row(&slots, ply, visible);
+    for (row, pair) in slots.chunks(2).enumerate().skip(first).take(visible) {
+        let line = row - first + 1;
+        draw_text(&format!("{}.", row + 1), font_size, [columns[0], baseline(line)], glyphs, context, graphics);
+        for (column, slot) in pair.iter().enumerate() {
+            if slot.ply == Some(ply) && ply > 0 {
+                highlight(column, line, graphics);
+            }
+            draw_text(&slot.text, font_size, [columns[column + 1], baseline(line)], glyphs, context, graphics);
+        }
+    }
+}
+
+fn draw_banner(text: &str, size: f64, glyphs: &mut Glyphs, context: Context, graphics: &mut WgpuGraphics) {
+    rectangle([0.0, 0.0, 0.0, 0.7], [0.0, size * 0.42, size, size * 0.16], context.transform, graphics);
+    draw_centred(text, (size / 16.0) as u32, size * 0.52, size, glyphs, context, graphics);
+}
+
+fn draw_menu(gui: &Gui, size: f64, glyphs: &mut Glyphs, context: Context, graphics: &mut WgpuGraphics) {
+    rectangle([0.0, 0.0, 0.0, 0.8], [0.0, 0.0, size, size], context.transform, graphics);
+    let title = match gui.screen {
+        Screen::Start => "Othello",
+        Screen::Players => "Players",
+        _ => "Menu",
+    };
+    draw_centred(title, (size / 12.0) as u32, size * 0.17, size, glyphs, context, graphics);
+    let font_size = (size / 22.0) as u32;
+    for (line, item) in gui.menu_items().iter().enumerate() {
+        let top = menu_line_top(line, size);
+        if line == gui.selected {
+            let highlight = [1.0, 1.0, 1.0, 0.2];
+            rectangle(highlight, [size * 0.1, top, size * 0.8, size * 0.1], context.transform, graphics);
+        }
+        draw_centred(&gui.label(*item), font_size, top + size * 0.065, size, glyphs, context, graphics);
+    }
+}
+
+pub fn run(game: Option<Game>) {
+    let fresh = game.is_none();
+    let mut gui = Gui::new(game);
+    if fresh {
+        gui.autosaved = autosaved_game();
+    }
+    let mut autosave = Autosave::from_settings(&gui.controller);
+    // the size of the board, which is as high as the window
+    let mut size = settings().window_size as f64;
+    let mut mouse = [0.0; 2];
+    let mut wait_before_ai_move = true;
+    let width = (settings().window_size as f64 * WINDOW_WIDTH) as u32;
+    let mut window: PistonWindow =
+        WindowSettings::new("Othello", [width, settings().window_size]).build().unwrap();
+    let mut glyphs = window.load_builtin_font(BuiltInFont::FiraSansRegular, TextureSettings::new()).unwrap();
+
+    /* GAME LOOP */
+    while let Some(event) = window.next() {
+        if let Some(Button::Mouse(MouseButton::Left)) = event.press_args() {
+            gui.click(mouse, size);
+            wait_before_ai_move = true;
+        } else if let Some(m) = event.mouse_cursor_args() {
+            mouse = m;
+        } else if let Some(Button::Keyboard(key)) = event.press_args() {
+            gui.handle_key(key);
+            wait_before_ai_move = true;
+        }
+        if gui.quit {
+            break;
+        }
+        size = window.size().height.min(window.size().width / WINDOW_WIDTH);
+        // while going through the moves taken back the AI waits, until a new move is played
+        let ai_may_move = gui.screen == Screen::Playing && !gui.controller.can_redo();
+        if ai_may_move && !wait_before_ai_move && gui.controller.ai_move().is_some() {
+            print_game_information(&gui.controller.game);
+        }
+        autosave.update(&gui.controller);
+        gui.update_analysis();
+        gui.update_evaluations();
+        if event.render_args().is_some() {
+            // we have rendered the updated board, let the computer calculate move (and freeze the screen).
+            wait_before_ai_move = false;
+        }
+
+        /* DRAWING */
+
+        let theme = settings().theme.colours();
+        window.draw_2d(&event, |context, graphics, _device| {
+            clear(theme.background, graphics);
+            draw_coordinates(size, &mut glyphs, context, graphics);
+            let (offset, board_size) = board_area(size);
+            let board_context = context.trans(offset, offset);
+            draw_board(&gui.controller, &theme, board_size, board_context, graphics);
+            draw_analysis(&gui, board_size, &mut glyphs, board_context, graphics);
+            draw_hints(&gui, &theme, board_size, &mut glyphs, board_context, graphics);
+            if gui.screen == Screen::Playing {
+                draw_flip_preview(&gui, mouse, size, context, graphics);
+            }
+            draw_panel(&gui, size, &mut glyphs, context, graphics);
+            draw_evaluation(&gui, &theme, size, context, graphics);
+            draw_move_list(&gui.controller, size, &mut glyphs, context, graphics);
+            if let Some(banner) = result_banner(&gui.controller.game) {
+                draw_banner(&banner, size, &mut glyphs, context, graphics);
+            }
+            if gui.screen != Screen::Playing {
+                draw_menu(&gui, size, &mut glyphs, context, graphics);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::game_from_transcript;
+
+    fn start_screen() -> Gui {
+        Gui::new(None)
+    }
+
+    #[test]
+    fn start_screen_sets_up_the_chosen_game() {
+        let mut gui = start_screen();
+        assert_eq!(gui.screen, Screen::Start);
+        gui.options.sides = [Side::Ai(1), Side::Human];
+        gui.handle_key(Key::Right);
+        assert_eq!(gui.label(Item::Side(false)), "Black: AI level 2");
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Left);
+        assert_eq!(gui.label(Item::Side(true)), "White: AI level 10");
+        gui.handle_key(Key::Left);
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Return);
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Right);
+        assert_eq!(gui.label(Item::Variant), "Start position: parallel");
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Return);
+
+        assert_eq!(gui.screen, Screen::Playing);
+        let controller = &gui.controller;
+        assert_eq!(controller.players, [Player::Ai, Player::Ai]);
+        assert_eq!(controller.ai_depths, [2, 9]);
+        assert_eq!(controller.game.board_essential.white_turn, !settings().white_is_starting);
+        let board = &controller.game.board_essential.board;
+        assert_eq!((board[3][3], board[4][3]), (Colour::BLACK, Colour::BLACK));
+        assert_eq!((board[3][4], board[4][4]), (Colour::WHITE, Colour::WHITE));
+    }
+
+    #[test]
+    fn variants_start_with_two_discs_each() {
+        // the colours on d4, e4, d5 and e5
+        let centre = |variant: Variant, white_first: bool| {
+            let board = variant.board(white_first);
+            ["d4", "e4", "d5", "e5"].map(|square| {
+                let square: Square = square.parse().unwrap();
+                board[square.x][square.y]
+            })
+        };
+        let (black, white) = (Colour::BLACK, Colour::WHITE);
+        assert_eq!(centre(Variant::Standard, false), [white, black, black, white]);
+        assert_eq!(centre(Variant::Standard, true), [black, white, white, black]);
+        assert_eq!(centre(Variant::Swapped, false), [black, white, white, black]);
+        assert_eq!(centre(Variant::Swapped, true), [white, black, black, white]);
+        assert_eq!(centre(Variant::Parallel, false), [black, black, white, white]);
+        assert_eq!(centre(Variant::Parallel, true), [black, black, white, white]);
+        for variant in [Variant::Standard, Variant::Swapped, Variant::Parallel] {
+            assert_eq!(variant.next(true).next(false), variant);
+        }
+    }
+
+    #[test]
+    fn menu_resigns_for_the_human() {
+        let mut gui = Gui::new(Some(crate::init_game()));
+        gui.controller.players = [Player::Ai, Player::Human];
+        gui.handle_key(Key::Escape);
+        assert_eq!(gui.screen, Screen::Menu);
+        let resign = items(Screen::Menu).iter().position(|item| *item == Item::Resign).unwrap();
+        gui.click([0.0, menu_line_top(resign, 500.0) + 1.0], 500.0);
+        assert_eq!(gui.screen, Screen::Playing);
+        let game = &gui.controller.game;
+        assert!(game.board_essential.game_over);
+        assert_eq!(game.board_essential.winner, Colour::BLACK);
+        assert_eq!(game.info.result.as_deref(), Some("+64.000:r"));
+        assert_eq!(gui.resigning_side(), None);
+    }
+
+    #[test]
+    fn changing_players_during_a_game() {
+        let mut gui = Gui::new(Some(crate::init_game()));
+        gui.handle_key(Key::Escape);
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Return);
+        assert_eq!(gui.screen, Screen::Players);
+        gui.handle_key(Key::Down);
+        gui.handle_key(Key::Right);
+        assert_eq!(gui.controller.players[1], Player::Ai);
+        assert_eq!(gui.controller.ai_depths[1], 1);
+        gui.handle_key(Key::Escape);
+        assert_eq!(gui.screen, Screen::Playing);
+    }
+
+    #[test]
+    fn panel_shows_discs_mobility_and_turn() {
+        let controller = Controller::new(game_from_transcript("c4").unwrap(), [Player::Human, Player::Ai], 1);
+        assert_eq!(side_lines(&controller, false), ["Black 1".to_string(), "human, 3 moves".to_string()]);
+        assert_eq!(side_lines(&controller, true), ["White 4".to_string(), "AI level 1, 3 moves".to_string()]);
+        assert_eq!(status_lines(&controller), vec!["Black to move"]);
+        assert_eq!(result_banner(&controller.game), None);
+    }
+
+    #[test]
+    fn panel_reports_passes_and_the_result() {
+        // after a1 white's only disc is out of reach, so white passes and c8 ends the game
+        let position = format!("-OX{}XO------ X", "-".repeat(53));
+        let mut controller = Controller::new(
+            crate::game_from_board_essentials(position.parse().unwrap()),
+            [Player::Human, Player::Human],
+            1,
+        );
+        controller.play("a1".parse().unwrap()).unwrap();
+        assert_eq!(status_lines(&controller), vec!["White had to pass", "Black to move"]);
+        assert_eq!(side_lines(&controller, true)[1], "human, 0 moves");
+        controller.play("c8".parse().unwrap()).unwrap();
+        assert_eq!(status_lines(&controller), vec!["Game over"]);
+        assert_eq!(result_banner(&controller.game).as_deref(), Some("Black wins 6 - 0"));
+
+        controller.undo();
+        controller.resign(false);
+        assert_eq!(result_banner(&controller.game).as_deref(), Some("White wins, black resigned"));
+    }
+
+    #[test]
+    fn keys_go_through_the_moves() {
+        let mut gui = Gui::new(Some(game_from_transcript("c4c3c2").unwrap()));
+        gui.controller.players = [Player::Ai, Player::Human];
+        gui.handle_key(Key::U);
+        // back to white's (the human's) last turn, before c2
+        assert_eq!(gui.controller.game.moves.len(), 2);
+        gui.handle_key(Key::Home);
+        assert!(gui.controller.game.moves.is_empty());
+        gui.handle_key(Key::End);
+        assert_eq!(gui.controller.game.moves.len(), 3);
+        assert_eq!(gui.controller.game.last_placed, "c2".parse().unwrap());
+    }
+
+    #[test]
+    fn start_screen_offers_the_unfinished_game() {
+        let mut gui = start_screen();
+        assert_eq!(gui.menu_items()[0], Item::Side(false));
+        let mut game = game_from_transcript("c4c3").unwrap();
+        game.info.black_player = Some("othello level 3".to_string());
+        game.info.white_player = Some("human".to_string());
+        gui.autosaved = Some(game);
+        assert_eq!(gui.label(gui.menu_items()[0]), "Resume unfinished game (2 moves)");
+        gui.handle_key(Key::Return);
+
+        assert_eq!(gui.screen, Screen::Playing);
+        assert_eq!(gui.controller.game.moves.len(), 2);
+        assert_eq!(gui.controller.players, [Player::Ai, Player::Human]);
+        assert_eq!(gui.controller.ai_depths[0], 3);
+        gui.open(Screen::Start);
+        assert_eq!(gui.menu_items()[0], Item::Side(false));
+    }
+
+    #[test]
+    fn move_list_shows_passes_and_goes_to_the_move_clicked() {
+        // as in panel_reports_passes_and_the_result, white passes after a1
+        let position = format!("-OX{}XO------ X", "-".repeat(53));
+        let mut gui = Gui::new(Some(crate::game_from_board_essentials(position.parse().unwrap())));
+        gui.controller.players = [Player::Human, Player::Human];
+        gui.controller.play("a1".parse().unwrap()).unwrap();
+        gui.controller.play("c8".parse().unwrap()).unwrap();
+        let slot = |ply: Option<usize>, text: &str| Slot { ply, text: text.to_string() };
+        let slots = move_slots(&gui.controller);
+        assert_eq!(slots, vec![slot(Some(1), "a1"), slot(None, "pass"), slot(Some(2), "c8")]);
+
+        let size = 560.0;
+        let [_, first, second] = move_columns(size);
+        let row = |row: f64| size * 0.02 + (row + 0.5) * move_row_height(size);
+        assert_eq!(move_at(&slots, 2, [first + 1.0, row(1.0)], size), Some(1));
+        assert_eq!(move_at(&slots, 2, [second + 1.0, row(1.0)], size), None);
+        assert_eq!(move_at(&slots, 2, [size * 0.5, row(1.0)], size), None);
+        gui.click([first + 1.0, row(0.0)], size);
+        assert!(gui.controller.game.moves.is_empty());
+        // the moves taken back stay in the list
+        gui.click([first + 1.0, row(2.0)], size);
+        assert_eq!(gui.controller.game.moves.len(), 2);
+    }
+
+    #[test]
+    fn move_list_scrolls_to_the_current_move() {
+        let slots: Vec<Slot> = (1..=60).map(|ply| Slot { ply: Some(ply), text: String::new() }).collect();
+        assert_eq!(first_move_row(&slots, 0, 20), 0);
+        assert_eq!(first_move_row(&slots, 40, 20), 0);
+        assert_eq!(first_move_row(&slots, 41, 20), 1);
+        assert_eq!(first_move_row(&slots, 60, 20), 10);
+    }
+
+    #[test]
+    fn hints_show_the_best_moves_and_are_counted() {
+        let mut gui = Gui::new(Some(crate::init_game()));
+        gui.controller.players = [Player::Human, Player::Human];
+        assert!(gui.hint_moves().is_empty());
+        gui.handle_key(Key::H);
+        let white = gui.controller.game.board_essential.white_turn;
+        let best = gui.hint_moves().to_vec();
+        assert_eq!(best.len(), 1);
+        assert!(legal_moves(&gui.controller.game.board_essential).contains(&best[0].0));
+        assert_eq!(hint_line(&best, white), Some(format!("Hint: {} {}", best[0].0, move_value(best[0].1, white))));
+        gui.handle_key(Key::H);
+        assert_eq!(gui.hint_moves().len(), 3);
+        assert_eq!(gui.hint_moves()[0], best[0]);
+        assert_eq!(gui.controller.game.info.hints[white as usize], 1);
+        assert_eq!(hints_taken(&gui.controller.game).as_deref(), Some("Hints taken: black 0, white 1"));
+
+        gui.controller.play(best[0].0.clone()).unwrap();
+        assert!(gui.hint_moves().is_empty());
+        assert_eq!(move_value(isize::MIN, false), "win");
+        assert_eq!(move_value(3, false), "-3");
+    }
+
+    #[test]
+    fn analysis_follows_the_position_on_the_board() {
+        let mut gui = Gui::new(Some(crate::init_game()));
+        gui.controller.players = [Player::Human, Player::Human];
+        gui.controller.depth = 1;
+        gui.update_analysis();
+        assert!(gui.analysis.is_none());
+        gui.handle_key(Key::A);
+        assert!(gui.analysis.as_ref().unwrap().is_for(&gui.controller.game.board_essential));
+        assert!(analysis_line(&gui).unwrap().starts_with("Analysis: "));
+
+        gui.controller.play("c4".parse().unwrap()).unwrap();
+        gui.update_analysis();
+        assert!(gui.analysis.as_ref().unwrap().is_for(&gui.controller.game.board_essential));
+        gui.handle_key(Key::Escape);
+        gui.update_analysis();
+        assert!(gui.analysis.is_none());
+        gui.handle_key(Key::Escape);
+        gui.handle_key(Key::A);
+        assert!(gui.analysis.is_none());
+
+        assert_eq!(rank_colour(0, 4), [0.1, 0.8, 0.1, 0.6]);
+        let worst = rank_colour(3, 4);
+        assert!(worst[0] > 0.8 && worst[1] < 0.2);
+        assert_eq!(rank_colour(0, 1), rank_colour(0, 4));
+    }
+
+    #[test]
+    fn evaluations_follow_the_line_and_the_graph_goes_to_a_move() {
+        let mut gui = Gui::new(Some(game_from_transcript("c4c3c2").unwrap()));
+        gui.controller.players = [Player::Human, Player::Human];
+        gui.controller.go_to(1);
+        gui.evaluator = Evaluator::new(1);
+        let started = std::time::Instant::now();
+        gui.update_evaluations();
+        while gui.evaluations.iter().any(Option::is_none) {
+            assert!(started.elapsed() < std::time::Duration::from_secs(30), "evaluations missing");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            gui.update_evaluations();
+        }
+        // the moves after the one on the board are evaluated as well
+        let positions = line_positions(&gui.controller);
+        assert_eq!(positions.len(), 4);
+        assert_eq!(gui.evaluations[3], Some(crate::minmax::search_value(&positions[3], 1)));
+
+        assert_eq!(white_share(0), 0.5);
+        assert_eq!((white_share(isize::MIN), white_share(isize::MAX)), (0.0, 1.0));
+        let size = 500.0;
+        let [x, y, width, _] = evaluation_graph_area(size);
+        assert_eq!(graph_ply_at([x + width, y + 1.0], size, 4), Some(3));
+        assert_eq!(graph_ply_at([x - 1.0, y + 1.0], size, 4), None);
+        gui.click([x + width * 0.6, y + 1.0], size);
+        assert_eq!(gui.controller.game.moves.len(), 2);
+
+        // going along the line keeps the positions, another move changes them
+        gui.controller.go_to(3);
+        gui.update_evaluations();
+        assert_eq!(gui.evaluated_line.as_ref().unwrap().1, gui.controller.line_moves());
+        gui.controller.go_to(1);
+        gui.controller.play("e3".parse().unwrap()).unwrap();
+        gui.update_evaluations();
+        assert_eq!(gui.line_positions.len(), 3);
+        assert_eq!(gui.line_positions[2].board, gui.controller.game.board_essential.board);
+        assert_eq!(gui.evaluations.len(), 3);
+    }
+
+    #[test]
+    fn pointing_at_a_legal_move_previews_its_flips() {
+        let mut gui = Gui::new(Some(crate::init_game()));
+        gui.controller.players = [Player::Human, Player::Human];
+        let size = 500.0;
+        let (offset, board_size) = board_area(size);
+        let centre = |x: usize, y: usize| {
+            let dist_per_block = board_size / BOARD_SIZE as f64;
+            [offset + (x as f64 + 0.5) * dist_per_block, offset + (y as f64 + 0.5) * dist_per_block]
+        };
+        assert_eq!(square_at(centre(2, 3), size), Some("c4".parse().unwrap()));
+        assert_eq!(square_at([offset / 2.0, offset / 2.0], size), None);
+        assert_eq!(square_at([size - offset / 2.0, size / 2.0], size), None);
+
+        let c4: Square = "c4".parse().unwrap();
+        assert_eq!(flip_preview(&gui.controller, &c4), Some(&["d4".parse().unwrap()][..]));
+        assert_eq!(flip_preview(&gui.controller, &"a1".parse().unwrap()), None);
+        gui.controller.players = [Player::Human, Player::Ai];
+        assert_eq!(flip_preview(&gui.controller, &c4), None);
+
+        gui.controller.players = [Player::Human, Player::Human];
+        gui.click(centre(2, 3), size);
+        assert_eq!(gui.controller.game.moves, vec![c4]);
+    }
+}
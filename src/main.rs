@@ -1,15 +1,34 @@
-use piston_window::*;
+mod analysis;
+mod bench;
+mod book;
+mod controller;
+mod endgame;
+mod ffo;
+mod ggf;
+mod gui;
 mod minmax;
-use minmax::get_for_whoever_best_move;
+mod nboard;
+mod notation;
+mod perft;
+mod position;
+#[cfg(test)]
+mod rules_tests;
+mod savegame;
+mod settings;
+mod terminal;
+mod text_protocol;
+mod tournament;
+mod tui;
+mod variations;
+mod wthor;
+use settings::settings;
 
 const BOARD_SIZE: usize = 8;
-const WHITE_IS_STARTING: bool = true;
-const AI_COLOUR: Colour = Colour::BLACK;
-const DEPTH: usize = 5;
 const STARING_STONE: usize = 4;
-const WINDOW_SIZE: u32 = 500;
 
-#[derive(Clone, Copy)]
+// named like constants, as the colours of the squares
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 enum Colour {
     WHITE,
     BLACK,
@@ -27,8 +46,30 @@ pub struct Game {
     board_essential: BoardEssentials,
 
     prev_boards: Vec<([[Colour; BOARD_SIZE]; BOARD_SIZE], bool)>,
+    // the moves played so far, one per entry in prev_boards. Passes are not stored
+    moves: Vec<Square>,
     last_placed: Square,
     flipped_tiles_from_move: Vec<Square>,
+    info: GameInfo,
+}
+
+// Who played the game and under which conditions, as exchanged in game records.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct GameInfo {
+    black_player: Option<String>,
+    white_player: Option<String>,
+    black_rating: Option<f64>,
+    white_rating: Option<f64>,
+    time_control: Option<String>,
+    place: Option<String>,
+    date: Option<String>,
+    // GGF style: black's disc margin, e.g. "+18.000", optionally followed by ":r" (resigned)
+    // or ":t" (time ran out)
+    result: Option<String>,
+    // the moves tried besides the game, see variations.rs
+    variations: Option<String>,
+    // the hints black and white asked for
+    hints: [usize; 2],
 }
 
 #[derive(Clone)]
@@ -41,197 +82,189 @@ pub struct BoardEssentials {
     winner: Colour,
 }
 
-fn init_game() -> Game {
-    let board = set_up_board();
-    let board_ess = BoardEssentials {
-        board: board,
-        white_turn: WHITE_IS_STARTING,
+fn init_board_essentials() -> BoardEssentials {
+    return start_board_essentials(settings().white_is_starting);
+}
+
+fn start_board_essentials(white_is_starting: bool) -> BoardEssentials {
+    let board = set_up_board(white_is_starting);
+    let mut board_ess = BoardEssentials {
+        board,
+        white_turn: white_is_starting,
         possible_moves: vec![],
         amount_of_stone: STARING_STONE,
         game_over: false,
         winner: Colour::EMPTY, //empty indicates draw. Value only use-able if game_over == true
     };
-    let mut game = Game {
-        board_essential: board_ess,
+    board_ess.possible_moves = get_all_possible_moves(&board_ess).0;
+    return board_ess;
+}
+
+// Sets up an arbitrary position. Like after a move, the turn passes to the other side if the side
+// to move has no legal move, and the game is over if neither side has one.
+fn board_essentials_from_board(board: [[Colour; BOARD_SIZE]; BOARD_SIZE], white_turn: bool) -> BoardEssentials {
+    let (black, white) = count_stones(&board);
+    let mut board_ess = BoardEssentials {
+        board,
+        white_turn,
+        possible_moves: vec![],
+        amount_of_stone: black + white,
+        game_over: false,
+        winner: Colour::EMPTY,
+    };
+    let (possible_moves, a_move_exists) = get_all_possible_moves(&board_ess);
+    board_ess.possible_moves = possible_moves;
+    if !a_move_exists {
+        board_ess.white_turn = !white_turn;
+        let (possible_moves, a_move_exists) = get_all_possible_moves(&board_ess);
+        board_ess.possible_moves = possible_moves;
+        if !a_move_exists {
+            board_ess.white_turn = white_turn;
+            board_ess.game_over = true;
+            board_ess.winner = get_winner(&board);
+        }
+    }
+    return board_ess;
+}
+
+fn init_game() -> Game {
+    return game_from_board_essentials(init_board_essentials());
+}
+
+fn game_from_board_essentials(board_essential: BoardEssentials) -> Game {
+    return Game {
+        board_essential,
         prev_boards: Vec::with_capacity(BOARD_SIZE * BOARD_SIZE),
+        moves: Vec::with_capacity(BOARD_SIZE * BOARD_SIZE),
         last_placed: Square { x: 0, y: 0 },
         flipped_tiles_from_move: Vec::new(),
-
+        info: GameInfo::default(),
     };
-    game.board_essential.possible_moves = get_all_possible_moves(&game.board_essential).0;
-    return game
+}
+
+// The position the game started from, before any move in its history.
+fn start_position(game: &Game) -> ([[Colour; BOARD_SIZE]; BOARD_SIZE], bool) {
+    match game.prev_boards.first() {
+        Some(start) => *start,
+        None => (game.board_essential.board, game.board_essential.white_turn),
+    }
 }
 
 fn main() {
-    println!("Welcome to OTHELLO, the game");
-    let mut game = init_game();
-    let mut mouse_x = 0;
-    let mut mouse_y = 0;
-    let mut dist_per_block = 0.0;
-    let mut wait_before_ai_move = true;
-    let mut window: PistonWindow = WindowSettings::new("Othello", [WINDOW_SIZE, WINDOW_SIZE])
-        .build()
-        .unwrap();
-
-    /* GAME LOOP */
-    while let Some(event) = window.next() {
-        if let Some(Button::Mouse(MouseButton::Left)) = event.press_args() {
-            // we pressed a button.
-
-            if mouse_x < BOARD_SIZE
-                && mouse_y < BOARD_SIZE
-                && game.board_essential.possible_moves[mouse_x][mouse_y].len() > 0
-                && !game.board_essential.game_over
-            {
-                if player_turn(game.board_essential.white_turn) {
-                    do_move_and_print_info(mouse_x, mouse_y, &mut game);
-                    wait_before_ai_move = true;
-                }
-            }
-        } else if let Some(m) = event.mouse_cursor_args() {
-            dist_per_block = window.size().height.min(window.size().width) / BOARD_SIZE as f64;
-            mouse_x = (m[0] / dist_per_block) as usize;
-            mouse_y = (m[1] / dist_per_block) as usize;
-        } else if let Some(Button::Keyboard(key)) = event.press_args() {
-            if key == Key::U {
-                undo(&mut game);
-                wait_before_ai_move = true;
-            }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // the settings come before the command, what is left is the command and its arguments
+    let args = match settings::init_from_args(&args) {
+        Ok(rest) => rest,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
         }
-        if !wait_before_ai_move && !player_turn(game.board_essential.white_turn) && !game.board_essential.game_over {
-            let best = get_for_whoever_best_move(&game.board_essential).unwrap();
-            do_move_and_print_info(best.x, best.y, &mut game);
-        }
-        if let Some(_) = event.render_args() {
-            // we have rendered the updated board, let the computer calculate move (and freeze the screen).
-            wait_before_ai_move = false;
+    };
+    savegame::report_autosave_on_panic();
+    if !args.is_empty() && !args[0].starts_with("--") {
+        let result = match args[0].as_str() {
+            "bench" => bench::run_cli(&args[1..]),
+            "ffo" => ffo::run_cli(&args[1..]),
+            "nboard" => nboard::run_cli(&args[1..]),
+            "perft" => perft::run_cli(&args[1..]),
+            "play" => terminal::run_cli(&args[1..]),
+            "text" => text_protocol::run_cli(&args[1..]),
+            "tournament" => tournament::run_cli(&args[1..]),
+            "tui" => tui::run_cli(&args[1..]),
+            "wthor" => wthor::run_cli(&args[1..]),
+            other => Err(format!("unknown command '{}'", other)),
+        };
+        if let Err(error) = result {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
         }
+        return;
+    }
 
-        /* DRAWING */
-
-        window.draw_2d(&event, |context, graphics, _device| {
-            clear([0.5, 0.5, 0.5, 1.0], graphics);
-
-            // shading newly flipped and placed stones
-            // only if we have stored a prev board
-            if game.prev_boards.len() > 0 {
-                let new_placed_colour = [0.0, 0.0, 0.8, 1.0];
-                let new_flipped_colour = [0.5, 0.5, 1.0, 1.0];
-                let (x, y) = (game.last_placed.x, game.last_placed.y);
-                rectangle(
-                    new_placed_colour,
-                    [
-                        dist_per_block * x as f64,
-                        dist_per_block * y as f64,
-                        dist_per_block,
-                        dist_per_block,
-                    ],
-                    context.transform,
-                    graphics,
-                );
-                for sq in game.flipped_tiles_from_move.iter() {
-                    rectangle(
-                        new_flipped_colour,
-                        [
-                            dist_per_block * sq.x as f64,
-                            dist_per_block * sq.y as f64,
-                            dist_per_block,
-                            dist_per_block,
-                        ],
-                        context.transform,
-                        graphics,
-                    );
-                }
+    println!("Welcome to OTHELLO, the game");
+    // without a game to continue the window opens on the start screen
+    let game = if args.is_empty() {
+        None
+    } else {
+        match start_game_from_args(&args) {
+            Ok(game) => Some(game),
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
             }
+        }
+    };
+    gui::run(game);
+}
 
-            let black = [0.0, 0.0, 0.0, 1.0];
-
-            for i in 1..(BOARD_SIZE + 1) {
-                let temp = dist_per_block * i as f64;
-                line(
-                    black,
-                    2.0,
-                    [0.0, temp, dist_per_block * BOARD_SIZE as f64, temp],
-                    context.transform,
-                    graphics,
-                );
-                line(
-                    black,
-                    2.0,
-                    [temp, 0.0, temp, dist_per_block * BOARD_SIZE as f64],
-                    context.transform,
-                    graphics,
-                );
-            }
-            let white = [1.0; 4];
-            for (x, column) in game.board_essential.board.iter().enumerate() {
-                for (y, colour) in column.iter().enumerate() {
-                    match colour {
-                        Colour::BLACK => ellipse(
-                            black,
-                            [
-                                (x as f64 + 0.2) * dist_per_block,
-                                (y as f64 + 0.2) * dist_per_block,
-                                dist_per_block * 0.6,
-                                dist_per_block * 0.6,
-                            ],
-                            context.transform,
-                            graphics,
-                        ),
-                        Colour::WHITE => ellipse(
-                            white,
-                            [
-                                (x as f64 + 0.2) * dist_per_block,
-                                (y as f64 + 0.2) * dist_per_block,
-                                dist_per_block * 0.6,
-                                dist_per_block * 0.6,
-                            ],
-                            context.transform,
-                            graphics,
-                        ),
-                        _ => (),
-                    }
-                }
-            }
-            if player_turn(game.board_essential.white_turn) {
-                let green = [0.0, 0.5, 0.0, 1.0];
-                for (x, outer_vec) in game.board_essential.possible_moves.iter().enumerate() {
-                    for (y, inner_vec) in outer_vec.iter().enumerate() {
-                        if inner_vec.len() > 0 {
-                            //this is a valid move
-                            ellipse(
-                                green,
-                                [
-                                    (x as f64 + 0.4) * dist_per_block,
-                                    (y as f64 + 0.4) * dist_per_block,
-                                    dist_per_block * 0.2,
-                                    dist_per_block * 0.2,
-                                ],
-                                context.transform,
-                                graphics,
-                            )
-                        }
-                    }
-                }
-            }
-        });
+// Options for the window: `--moves f5d6c3` continues the game after the given moves,
+// `--position "<position>"` starts from a position string, `--ggf <file>` continues the first
+// game in a GGF file, `--load <file>` a saved game (GGF or a transcript) and `--resume` the game
+// left unfinished in the autosave file.
+fn start_game_from_args(args: &[String]) -> Result<Game, String> {
+    let mut game = init_game();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--moves" => {
+                let moves = iter.next().ok_or("missing value for --moves")?;
+                game = notation::game_from_transcript(moves).map_err(|e| e.to_string())?;
+            }
+            "--position" => {
+                let position = iter.next().ok_or("missing value for --position")?;
+                let board_essential = position.parse().map_err(|e: position::PositionError| e.to_string())?;
+                game = game_from_board_essentials(board_essential);
+            }
+            "--load" => {
+                let path = iter.next().ok_or("missing value for --load")?;
+                game = savegame::load_game(std::path::Path::new(path))?;
+            }
+            "--resume" => {
+                game = savegame::autosaved_game().ok_or("there is no unfinished autosaved game")?;
+            }
+            "--ggf" => {
+                let path = iter.next().ok_or("missing value for --ggf")?;
+                let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+                let records = ggf::parse_ggf(&text).map_err(|e| e.to_string())?;
+                game = records[0].game.clone();
+            }
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
     }
+    Ok(game)
 }
 
+// Takes back the last move, including the highlighting of the move before it.
 fn undo(game: &mut Game) {
-    match game.prev_boards.pop() {
-        Some(old_board) => {
-            game.board_essential.board = old_board.0;
-            game.board_essential.white_turn = old_board.1;
-            game.board_essential.possible_moves = get_all_possible_moves(&game.board_essential).0;
-            game.board_essential.amount_of_stone -= 1;
-            game.board_essential.game_over = false;
+    if let Some(old_board) = game.prev_boards.pop() {
+        game.moves.pop();
+        game.board_essential.board = old_board.0;
+        game.board_essential.white_turn = old_board.1;
+        game.board_essential.possible_moves = get_all_possible_moves(&game.board_essential).0;
+        game.board_essential.amount_of_stone -= 1;
+        game.board_essential.game_over = false;
+        game.board_essential.winner = Colour::EMPTY;
+
+        // the discs the previous move flipped are the ones that changed colour with it
+        game.flipped_tiles_from_move.clear();
+        match (game.moves.last(), game.prev_boards.last()) {
+            (Some(last), Some((before, _))) => {
+                game.last_placed = last.clone();
+                for (x, column) in before.iter().enumerate() {
+                    for (y, colour) in column.iter().enumerate() {
+                        if *colour != Colour::EMPTY && *colour != game.board_essential.board[x][y] {
+                            game.flipped_tiles_from_move.push(Square { x, y });
+                        }
+                    }
+                }
+            }
+            _ => game.last_placed = Square { x: 0, y: 0 },
         }
-        None => (),
     }
 }
 
-fn get_winner(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE]) -> Colour {
+// returns (black, white)
+fn count_stones(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE]) -> (usize, usize) {
     let mut black_count = 0;
     let mut white_count = 0;
     for outer in board {
@@ -243,6 +276,11 @@ fn get_winner(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE]) -> Colour {
             }
         }
     }
+    (black_count, white_count)
+}
+
+fn get_winner(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE]) -> Colour {
+    let (black_count, white_count) = count_stones(board);
 
     if black_count == white_count {
         Colour::EMPTY
@@ -253,31 +291,22 @@ fn get_winner(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE]) -> Colour {
     }
 }
 
-fn player_turn(white_turn: bool) -> bool {
-    match AI_COLOUR {
-        Colour::EMPTY => return true,       // No one is AI
-        Colour::WHITE => return !white_turn,// AI is white
-        Colour::BLACK => return white_turn, // AI is black
-    }
-}
-
-fn set_up_board() -> [[Colour; BOARD_SIZE]; BOARD_SIZE] {
+// The side moving first gets e4 and d5, as black in the standard opening, so transcripts and the
+// book are played the same whoever starts.
+fn set_up_board(white_is_starting: bool) -> [[Colour; BOARD_SIZE]; BOARD_SIZE] {
+    let (first, second) = if white_is_starting { (Colour::WHITE, Colour::BLACK) } else { (Colour::BLACK, Colour::WHITE) };
     let mut board = [[Colour::EMPTY; BOARD_SIZE]; BOARD_SIZE];
-    board[BOARD_SIZE / 2 - 1][BOARD_SIZE / 2 - 1] = Colour::BLACK;
-    board[BOARD_SIZE / 2][BOARD_SIZE / 2 - 1] = Colour::WHITE;
-    board[BOARD_SIZE / 2 - 1][BOARD_SIZE / 2] = Colour::WHITE;
-    board[BOARD_SIZE / 2][BOARD_SIZE / 2] = Colour::BLACK;
+    board[BOARD_SIZE / 2 - 1][BOARD_SIZE / 2 - 1] = second;
+    board[BOARD_SIZE / 2][BOARD_SIZE / 2 - 1] = first;
+    board[BOARD_SIZE / 2 - 1][BOARD_SIZE / 2] = first;
+    board[BOARD_SIZE / 2][BOARD_SIZE / 2] = second;
     return board;
 }
 
-fn do_move_and_print_info(x: usize, y: usize, game: &mut Game) {
-    do_move(x, y, game);
-    print_game_information(game);
-}
-
 fn do_move(x: usize, y: usize, game: &mut Game) {
-    game.prev_boards.push((game.board_essential.board.clone(), game.board_essential.white_turn));
-    game.last_placed = Square { x: x, y: y };
+    game.prev_boards.push((game.board_essential.board, game.board_essential.white_turn));
+    game.moves.push(Square { x, y });
+    game.last_placed = Square { x, y };
     game.flipped_tiles_from_move = game.board_essential.possible_moves[x][y].clone();
     
     do_move_essentials(x, y, &mut game.board_essential)
@@ -296,7 +325,7 @@ fn do_move_essentials(x: usize, y: usize, board_essential: &mut BoardEssentials)
     board_essential.board[x][y] = colour;
 
     board_essential.white_turn = !board_essential.white_turn;
-    let temp = get_all_possible_moves(&board_essential);
+    let temp = get_all_possible_moves(board_essential);
     board_essential.possible_moves = temp.0;
     let a_move_exists = temp.1;
     board_essential.amount_of_stone += 1;
@@ -306,7 +335,7 @@ fn do_move_essentials(x: usize, y: usize, board_essential: &mut BoardEssentials)
         board_essential.winner = get_winner(&board_essential.board);
     } else if !a_move_exists {
         board_essential.white_turn = !board_essential.white_turn;
-        let temp = get_all_possible_moves(&board_essential);
+        let temp = get_all_possible_moves(board_essential);
         board_essential.possible_moves = temp.0;
         let a_move_exists = temp.1;
         if !a_move_exists {
@@ -329,15 +358,32 @@ fn print_game_information(game: &Game) {
                 println!("Game is over. It's a draw, a rare occurance.");
             }
         }
+        println!("Transcript: {}", notation::transcript(game));
     } else {
         println!(
-            "Current game state:\n\tCurrent player to do a move: {}\n\tAmount of stones on table: {}",
+            "Current game state:\n\tLast move: {}\n\tCurrent player to do a move: {}\n\tAmount of stones on table: {}",
+            match game.moves.last() {
+                Some(last) => last.to_string(),
+                None => "-".to_string(),
+            },
             if game.board_essential.white_turn { "White" } else { "Black" },
             game.board_essential.amount_of_stone
         );
     }
 }
 
+fn legal_moves(board_essential: &BoardEssentials) -> Vec<Square> {
+    let mut moves = vec![];
+    for (x, column) in board_essential.possible_moves.iter().enumerate() {
+        for (y, flips) in column.iter().enumerate() {
+            if !flips.is_empty() {
+                moves.push(Square { x, y });
+            }
+        }
+    }
+    moves
+}
+
 fn get_all_possible_moves(board_essential: &BoardEssentials) -> (Vec<Vec<Vec<Square>>>, bool) {
     let mut result: Vec<Vec<Vec<Square>>> = Vec::with_capacity(BOARD_SIZE);
     let mut a_move_exists = false;
@@ -348,15 +394,8 @@ fn get_all_possible_moves(board_essential: &BoardEssentials) -> (Vec<Vec<Vec<Squ
     for (x, row) in board_essential.board.iter().enumerate() {
         for (y, square) in row.iter().enumerate() {
             match (square, board_essential.white_turn) {
-                (Colour::BLACK, false) => {
-                    if insert_square_possible_moves(&board_essential, x, y, &mut result) {
-                        a_move_exists = true;
-                    }
-                }
-                (Colour::WHITE, true) => {
-                    if insert_square_possible_moves(&board_essential, x, y, &mut result) {
-                        a_move_exists = true;
-                    }
+                (Colour::BLACK, false) | (Colour::WHITE, true) => {
+                    a_move_exists |= insert_square_possible_moves(board_essential, x, y, &mut result);
                 }
                 _ => (),
             }
@@ -369,7 +408,7 @@ fn insert_square_possible_moves(
     board_essential: &BoardEssentials,
     x: usize,
     y: usize,
-    dump: &mut Vec<Vec<Vec<Square>>>,
+    dump: &mut [Vec<Vec<Square>>],
 ) -> bool {
     let mut squares_passed: Vec<Square> = vec![];
     let mut a_move_exists = false;
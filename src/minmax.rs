@@ -1,29 +1,51 @@
-use crate::{Colour, Square, BOARD_SIZE, DEPTH, BoardEssentials, do_move_essentials};
+use crate::{Colour, Square, BOARD_SIZE, BoardEssentials, do_move_essentials, legal_moves};
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
 
-pub fn get_for_whoever_best_move(board_essential: &BoardEssentials) -> Option<Square> {
+thread_local! {
+    // Positions visited by max_search and min_search on this thread, for benchmarking.
+    static NODES: Cell<u64> = const { Cell::new(0) };
+}
+
+pub fn reset_node_count() {
+    NODES.with(|nodes| nodes.set(0));
+}
+
+pub fn node_count() -> u64 {
+    NODES.with(|nodes| nodes.get())
+}
+
+pub fn get_for_whoever_best_move(board_essential: &BoardEssentials, depth: usize) -> Option<Square> {
+    return best_move_and_value(board_essential, depth).map(|(square, _)| square);
+}
+
+// The best move with the value the same search gave it, from white's point of view like
+// search_value, so the value costs no second search.
+pub fn best_move_and_value(board_essential: &BoardEssentials, depth: usize) -> Option<(Square, isize)> {
     if board_essential.game_over {
         return None;
     } else if board_essential.white_turn {
-        return Some(get_best_move_for_white(board_essential));
+        return Some(get_best_move_for_white(board_essential, depth));
     } else {
-        return Some(get_best_move_for_black(board_essential));
+        return Some(get_best_move_for_black(board_essential, depth));
     }
 }
 
-fn get_best_move_for_black(board_essential: &BoardEssentials) -> Square {
+fn get_best_move_for_black(board_essential: &BoardEssentials, depth: usize) -> (Square, isize) {
     // We need some code dupe, here. max_search and min_search doesnt return a move, and would be probably be a lot slower with keeping track of that
     // And since those functions are doing the heavy work we want them to be fast.
     let (mut minimum_for_best_move, mut best_x, mut best_y) = (isize::MAX, BOARD_SIZE, BOARD_SIZE);
     for x in 0..BOARD_SIZE {
         for y in 0..BOARD_SIZE {
-            if board_essential.possible_moves[x][y].len() == 0 {
+            if board_essential.possible_moves[x][y].is_empty() {
                 continue;
             }
             let mut clone = board_essential.clone();
             do_move_essentials(x, y, &mut clone);
             if clone.game_over {
                 match clone.winner {
-                    Colour::BLACK => return Square { x: x, y: y },
+                    Colour::BLACK => return (Square { x, y }, isize::MIN),
                     Colour::EMPTY => {
                         if minimum_for_best_move > 0 {
                             (minimum_for_best_move, best_x, best_y) = (0, x, y)
@@ -31,7 +53,7 @@ fn get_best_move_for_black(board_essential: &BoardEssentials) -> Square {
                     }
                     Colour::WHITE => {
                         if best_x == BOARD_SIZE {
-                            best_x = x;
+                            (best_x, best_y) = (x, y);
                         }
                         continue;
                     }
@@ -40,34 +62,38 @@ fn get_best_move_for_black(board_essential: &BoardEssentials) -> Square {
                 let value_of_move = max_search(
                     minimum_for_best_move,
                     clone,
-                    DEPTH.min(BOARD_SIZE * BOARD_SIZE - board_essential.amount_of_stone),
+                    depth.min(BOARD_SIZE * BOARD_SIZE - board_essential.amount_of_stone),
                 );
-                if value_of_move < minimum_for_best_move {
+                // a forced loss still has to return a legal move
+                if value_of_move < minimum_for_best_move || best_x == BOARD_SIZE {
                     (minimum_for_best_move, best_x, best_y) = (value_of_move, x, y)
                 }
             }
         }
     }
-    return Square {
-        x: best_x,
-        y: best_y,
-    };
+    return (
+        Square {
+            x: best_x,
+            y: best_y,
+        },
+        minimum_for_best_move,
+    );
 }
 
-fn get_best_move_for_white(board_essential: &BoardEssentials) -> Square {
+fn get_best_move_for_white(board_essential: &BoardEssentials, depth: usize) -> (Square, isize) {
     // I chose to do code-dupe, since otherwise i believe it would be confusing.
     // It's the same as the above but maximising instead
     let (mut max_for_best_move, mut best_x, mut best_y) = (isize::MIN, BOARD_SIZE, BOARD_SIZE);
     for x in 0..BOARD_SIZE {
         for y in 0..BOARD_SIZE {
-            if board_essential.possible_moves[x][y].len() == 0 {
+            if board_essential.possible_moves[x][y].is_empty() {
                 continue;
             }
             let mut clone = board_essential.clone();
             do_move_essentials(x, y, &mut clone);
             if clone.game_over {
                 match clone.winner {
-                    Colour::WHITE => return Square { x: x, y: y },
+                    Colour::WHITE => return (Square { x, y }, isize::MAX),
                     Colour::EMPTY => {
                         if max_for_best_move < 0 {
                             (max_for_best_move, best_x, best_y) = (0, x, y)
@@ -75,7 +101,7 @@ fn get_best_move_for_white(board_essential: &BoardEssentials) -> Square {
                     }
                     Colour::BLACK => {
                         if best_x == BOARD_SIZE {
-                            best_x = x;
+                            (best_x, best_y) = (x, y);
                         }
                         continue;
                     }
@@ -84,23 +110,92 @@ fn get_best_move_for_white(board_essential: &BoardEssentials) -> Square {
                 let value_of_move = min_search(
                     max_for_best_move,
                     clone,
-                    DEPTH.min(BOARD_SIZE * BOARD_SIZE - board_essential.amount_of_stone),
+                    depth.min(BOARD_SIZE * BOARD_SIZE - board_essential.amount_of_stone),
                 );
-                if value_of_move > max_for_best_move {
+                if value_of_move > max_for_best_move || best_x == BOARD_SIZE {
                     (max_for_best_move, best_x, best_y) = (value_of_move, x, y)
                 }
             }
         }
     }
-    return Square {
-        x: best_x,
-        y: best_y,
+    return (
+        Square {
+            x: best_x,
+            y: best_y,
+        },
+        max_for_best_move,
+    );
+}
+
+// Searches one ply deeper at a time up to `depth`, but only starts a search that is expected to
+// finish within `budget`, assuming each ply takes about six times as long as the one before.
+pub fn best_move_within(board_essential: &BoardEssentials, depth: usize, budget: Duration) -> Option<Square> {
+    let start = Instant::now();
+    let mut best = get_for_whoever_best_move(board_essential, 1)?;
+    let mut last_search = start.elapsed();
+    for depth in 2..=depth {
+        if start.elapsed() + last_search * 6 > budget {
+            break;
+        }
+        let search_start = Instant::now();
+        best = get_for_whoever_best_move(board_essential, depth)?;
+        last_search = search_start.elapsed();
+    }
+    Some(best)
+}
+
+// The value of the position searched `depth` plies deep, from white's point of view like
+// evaluate_game. A game won by white is isize::MAX and one won by black isize::MIN.
+pub fn search_value(board_essential: &BoardEssentials, depth: usize) -> isize {
+    if board_essential.game_over {
+        return match board_essential.winner {
+            Colour::WHITE => isize::MAX,
+            Colour::BLACK => isize::MIN,
+            Colour::EMPTY => 0,
+        };
+    }
+    if board_essential.white_turn {
+        max_search(isize::MAX, board_essential.clone(), depth)
+    } else {
+        min_search(isize::MIN, board_essential.clone(), depth)
+    }
+}
+
+// The legal moves with the search value of the position after each, `depth` plies deep in all,
+// best first for the side to move. Unlike get_for_whoever_best_move every move is searched fully,
+// so the values of the others can be shown as well.
+pub fn ranked_moves(board_essential: &BoardEssentials, depth: usize) -> Vec<(Square, isize)> {
+    ranked_moves_unless(board_essential, depth, || false).unwrap_or_default()
+}
+
+// As ranked_moves, but gives up (with None) as soon as `stop` says so between two moves.
+pub fn ranked_moves_unless(
+    board_essential: &BoardEssentials,
+    depth: usize,
+    stop: impl Fn() -> bool,
+) -> Option<Vec<(Square, isize)>> {
+    let mut moves = vec![];
+    for square in legal_moves(board_essential) {
+        if stop() {
+            return None;
+        }
+        let mut clone = board_essential.clone();
+        do_move_essentials(square.x, square.y, &mut clone);
+        let value = search_value(&clone, depth.saturating_sub(1));
+        moves.push((square, value));
+    }
+    if board_essential.white_turn {
+        moves.sort_by_key(|&(_, value)| Reverse(value));
+    } else {
+        moves.sort_by_key(|&(_, value)| value);
     }
+    Some(moves)
 }
 
 // invariant: if we are maximizing then any value higher than alpha is valid return.
 // looking for best move for white
 fn max_search(alpha: isize, board_essential: BoardEssentials, depth: usize) -> isize {
+    NODES.with(|nodes| nodes.set(nodes.get() + 1));
     if depth == 0 {
         return evaluate_game(&board_essential);
     }
@@ -109,7 +204,7 @@ fn max_search(alpha: isize, board_essential: BoardEssentials, depth: usize) -> i
     let mut move_found = false;
     for x in 0..BOARD_SIZE {
         for y in 0..BOARD_SIZE {
-            if board_essential.possible_moves[x][y].len() == 0 {
+            if board_essential.possible_moves[x][y].is_empty() {
                 continue;
             }
             move_found = true;
@@ -138,6 +233,7 @@ fn max_search(alpha: isize, board_essential: BoardEssentials, depth: usize) -> i
 }
 
 fn min_search(alpha: isize, board_essential: BoardEssentials, depth: usize) -> isize {
+    NODES.with(|nodes| nodes.set(nodes.get() + 1));
     if depth == 0 {
         return evaluate_game(&board_essential);
     }
@@ -146,7 +242,7 @@ fn min_search(alpha: isize, board_essential: BoardEssentials, depth: usize) -> i
     let mut move_found = false;
     for x in 0..BOARD_SIZE {
         for y in 0..BOARD_SIZE {
-            if board_essential.possible_moves[x][y].len() == 0 {
+            if board_essential.possible_moves[x][y].is_empty() {
                 continue;
             }
             move_found = true;
@@ -173,7 +269,7 @@ fn min_search(alpha: isize, board_essential: BoardEssentials, depth: usize) -> i
     return min;
 }
 
-fn evaluate_game(board_essential: &BoardEssentials) -> isize {
+pub fn evaluate_game(board_essential: &BoardEssentials) -> isize {
     let mut res = 0;
     for col in board_essential.board.iter() {
         for colour in col.iter() {
@@ -210,3 +306,60 @@ fn evaluate_game(board_essential: &BoardEssentials) -> isize {
 
     return res;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The position with the colours and the side to move swapped, for testing both sides.
+    fn swapped(position: &str) -> BoardEssentials {
+        let swapped: String = position
+            .chars()
+            .map(|c| match c {
+                'X' => 'O',
+                'O' => 'X',
+                c => c,
+            })
+            .collect();
+        swapped.parse().unwrap()
+    }
+
+    #[test]
+    fn the_best_move_comes_with_the_value_of_its_search() {
+        let game = crate::notation::game_from_transcript("f5d6c3d3c4f4").unwrap();
+        for depth in 1..=3 {
+            reset_node_count();
+            let best = get_for_whoever_best_move(&game.board_essential, depth).unwrap();
+            let nodes = node_count();
+            reset_node_count();
+            let (square, value) = best_move_and_value(&game.board_essential, depth).unwrap();
+            assert_eq!((square.clone(), node_count()), (best, nodes));
+            let mut after = game.board_essential.clone();
+            do_move_essentials(square.x, square.y, &mut after);
+            assert_eq!(value, search_value(&after, depth));
+        }
+    }
+
+    #[test]
+    fn a_move_that_loses_at_once_is_still_a_legal_move() {
+        // a1 is the only move and fills the board, with the other side far ahead
+        let position = format!("-OX{} X", "O".repeat(61));
+        for board_essential in [position.parse().unwrap(), swapped(&position)] {
+            for depth in [1, 3] {
+                assert_eq!(get_for_whoever_best_move(&board_essential, depth), Some("a1".parse().unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn a_forced_loss_still_returns_a_legal_move() {
+        // after a1, the only move, the other side plays h8 and wins with the board full
+        let position = format!("-OX{}X- X", "O".repeat(59));
+        for board_essential in [position.parse().unwrap(), swapped(&position)] {
+            assert!(!board_essential.game_over);
+            for depth in [1, 3] {
+                assert_eq!(get_for_whoever_best_move(&board_essential, depth), Some("a1".parse().unwrap()));
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::endgame::solve;
+use crate::minmax::{best_move_and_value, node_count, reset_node_count, search_value};
+use crate::settings::settings;
+use crate::{do_move, do_move_essentials, ggf, init_game, legal_moves, BoardEssentials, Game, Square, BOARD_SIZE};
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+// The NBoard engine protocol, so the engine can be used from the NBoard GUI and other tools that
+// speak it. Commands arrive on stdin one per line and the replies go to stdout:
+//   nboard <version>   -> set myname <name>
+//   set depth <n>         search depth for go and hint
+//   set game <ggf>        replace the game with a GGF record
+//   set contempt <n>      accepted and ignored
+//   move <move>           play a move, e.g. "move f5" or "move F5/1.00/2.5"; "PA" for a pass
+//   hint <n>           -> "search <move> <eval> 0 <depth>" for the best n moves, each search depth
+//   go                 -> "=== <move>/<eval>/<seconds>" and "nodestats <nodes> <seconds>"
+//   learn              -> learned
+//   ping <n>           -> pong <n>
+// While working the engine sends "status <text>", and an empty "status" when done. Evaluations are
+// in discs for the side to move. With few empty squares left the exact endgame solver is used, in
+// which case the depth shows as "100%".
+
+const NAME: &str = "othello";
+const ENDGAME_EMPTIES: usize = 14;
+
+struct Engine {
+    game: Game,
+    depth: usize,
+}
+
+// Search values are roughly discs already; decided games are shown as a 64 disc win or loss.
+fn disc_eval(value: isize, white_to_move: bool) -> f64 {
+    let white_eval = match value {
+        isize::MAX => 64.0,
+        isize::MIN => -64.0,
+        _ => value as f64,
+    };
+    if white_to_move {
+        white_eval
+    } else {
+        -white_eval
+    }
+}
+
+fn nboard_move(square: &Square) -> String {
+    square.to_string().to_uppercase()
+}
+
+fn empties(board_essential: &BoardEssentials) -> usize {
+    BOARD_SIZE * BOARD_SIZE - board_essential.amount_of_stone
+}
+
+// Every legal move with its evaluation for the side to move, best first.
+fn rank_moves(board_essential: &BoardEssentials, depth: usize, exact: bool) -> Vec<(Square, f64)> {
+    let mut ranked: Vec<(Square, f64)> = legal_moves(board_essential)
+        .into_iter()
+        .map(|square| {
+            let mut after = board_essential.clone();
+            do_move_essentials(square.x, square.y, &mut after);
+            let eval = if exact {
+                let score = solve(&after).score as f64;
+                if after.white_turn == board_essential.white_turn { score } else { -score }
+            } else {
+                disc_eval(search_value(&after, depth - 1), board_essential.white_turn)
+            };
+            (square, eval)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+impl Engine {
+    fn handle(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let argument = argument.trim();
+        match command {
+            "" => (),
+            "nboard" => writeln!(out, "set myname {}", NAME)?,
+            "set" => self.set(argument, out)?,
+            "move" => {
+                if let Err(error) = self.play(argument) {
+                    writeln!(out, "status error: {}", error)?;
+                }
+            }
+            "hint" => match argument.parse::<usize>() {
+                Ok(count) => self.hint(count, out)?,
+                Err(_) => writeln!(out, "status error: hint expects a number, got '{}'", argument)?,
+            },
+            "go" => self.go(out)?,
+            // there is no opening book to add the game to
+            "learn" => writeln!(out, "learned")?,
+            "ping" => writeln!(out, "pong {}", argument)?,
+            "quit" => return Ok(false),
+            _ => writeln!(out, "status error: unknown command '{}'", command)?,
+        }
+        Ok(true)
+    }
+
+    fn set(&mut self, argument: &str, out: &mut impl Write) -> io::Result<()> {
+        let (name, value) = argument.split_once(' ').unwrap_or((argument, ""));
+        match name {
+            "depth" => match value.trim().parse::<usize>() {
+                Ok(depth) if depth > 0 => self.depth = depth,
+                _ => writeln!(out, "status error: set depth expects a positive number, got '{}'", value)?,
+            },
+            "game" => match ggf::parse_ggf(value) {
+                Ok(records) if !records.is_empty() => self.game = records[0].game.clone(),
+                Ok(_) => writeln!(out, "status error: no game in set game")?,
+                Err(error) => writeln!(out, "status error: {}", error)?,
+            },
+            "contempt" => (),
+            _ => writeln!(out, "status error: unknown setting '{}'", name)?,
+        }
+        Ok(())
+    }
+
+    // do_move passes automatically, so a pass sent by the GUI has already happened here.
+    fn play(&mut self, text: &str) -> Result<(), String> {
+        let square_text = text.split('/').next().unwrap_or("").trim();
+        if square_text.eq_ignore_ascii_case("pa") {
+            return Ok(());
+        }
+        let square: Square = square_text.parse().map_err(|e| format!("{}", e))?;
+        if self.game.board_essential.game_over || !legal_moves(&self.game.board_essential).contains(&square) {
+            return Err(format!("{} is not a legal move", square));
+        }
+        do_move(square.x, square.y, &mut self.game);
+        Ok(())
+    }
+
+    fn hint(&mut self, count: usize, out: &mut impl Write) -> io::Result<()> {
+        let board_essential = &self.game.board_essential;
+        if board_essential.game_over {
+            return writeln!(out, "status");
+        }
+        writeln!(out, "status analysing")?;
+        if empties(board_essential) <= ENDGAME_EMPTIES {
+            for (square, eval) in rank_moves(board_essential, 0, true).iter().take(count) {
+                writeln!(out, "search {} {:.2} 0 100%", nboard_move(square), eval)?;
+            }
+        } else {
+            // deeper searches refine the hints that were already sent
+            for depth in 1..=self.depth {
+                for (square, eval) in rank_moves(board_essential, depth, false).iter().take(count) {
+                    writeln!(out, "search {} {:.2} 0 {}", nboard_move(square), eval, depth)?;
+                }
+                out.flush()?;
+            }
+        }
+        writeln!(out, "status")
+    }
+
+    fn go(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let board_essential = self.game.board_essential.clone();
+        if board_essential.game_over {
+            return writeln!(out, "status error: the game is over");
+        }
+        writeln!(out, "status thinking")?;
+        out.flush()?;
+        let start = Instant::now();
+        reset_node_count();
+        let (square, eval, nodes) = if empties(&board_essential) <= ENDGAME_EMPTIES {
+            let result = solve(&board_essential);
+            (result.best.expect("the game is not over"), result.score as f64, result.nodes)
+        } else {
+            let (square, value) = best_move_and_value(&board_essential, self.depth).expect("the game is not over");
+            (square, disc_eval(value, board_essential.white_turn), node_count())
+        };
+        let seconds = start.elapsed().as_secs_f64();
+        writeln!(out, "nodestats {} {:.3}", nodes, seconds)?;
+        writeln!(out, "=== {}/{:.2}/{:.3}", nboard_move(&square), eval, seconds)?;
+        writeln!(out, "status")
+    }
+}
+
+// Runs a protocol session until "quit" or the end of the input.
+pub fn run_session(input: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    let mut engine = Engine { game: init_game(), depth: settings().depth };
+    for line in input.lines() {
+        let keep_going = engine.handle(&line?, out)?;
+        out.flush()?;
+        if !keep_going {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Usage: othello nboard
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    if let Some(arg) = args.first() {
+        return Err(format!("unknown nboard argument '{}'", arg));
+    }
+    let stdin = io::stdin();
+    run_session(stdin.lock(), &mut io::stdout().lock()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(script: &str) -> Vec<String> {
+        let mut out = vec![];
+        run_session(script.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn handshake_and_ping() {
+        assert_eq!(session("nboard 2\nset depth 2\nping 1\n"), vec!["set myname othello", "pong 1"]);
+    }
+
+    #[test]
+    fn go_plays_a_legal_move_in_the_set_game() {
+        let script = "set depth 2\n\
+            set game (;GM[Othello]PC[NBoard]PB[a]PW[b]TY[8]BO[8 ---------------------------O*------*O--------------------------- *]B[F5]W[D6];)\n\
+            move c3\n\
+            go\n\
+            ping 7\n";
+        let lines = session(script);
+        let answer = lines.iter().find(|line| line.starts_with("=== ")).expect("no move played");
+        let square: Square = answer[4..6].to_lowercase().parse().unwrap();
+        let game = crate::notation::game_from_transcript("f5d6c3").unwrap();
+        assert!(legal_moves(&game.board_essential).contains(&square), "{}", answer);
+        assert!(lines.iter().any(|line| line.starts_with("nodestats ")));
+        assert_eq!(lines.last().unwrap(), "pong 7");
+    }
+
+    #[test]
+    fn hint_sends_the_best_moves_for_each_depth() {
+        let lines = session("set depth 2\nhint 3\n");
+        let searches: Vec<&String> = lines.iter().filter(|line| line.starts_with("search ")).collect();
+        // four moves at the start, so three hints per depth
+        assert_eq!(searches.len(), 6);
+        assert!(searches[5].ends_with(" 0 2"));
+        assert_eq!(lines.last().unwrap(), "status");
+    }
+
+    #[test]
+    fn rejects_illegal_moves_and_keeps_going() {
+        let lines = session("move a1\nmove f5/0.5/1.2\nmove f5\nping 2\n");
+        assert_eq!(lines, vec!["status error: a1 is not a legal move", "status error: f5 is not a legal move", "pong 2"]);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{do_move, init_game, legal_moves, Game, Square, BOARD_SIZE};
+use std::fmt;
+use std::str::FromStr;
+
+// Standard Othello notation. Columns are the letters a-h from left to right (x) and rows the
+// numbers 1-8 from top to bottom (y), so Square { x: 5, y: 4 } is "f5".
+// A transcript is the moves of a game written one after another, e.g. "f5d6c3d3c4". Passes are
+// left out since they are forced, although "pa" is accepted where a pass happened.
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseSquareError(pub String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' is not a square, expected a1 to h8", self.0)
+    }
+}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 2 {
+            return Err(ParseSquareError(s.to_string()));
+        }
+        let x = bytes[0].to_ascii_lowercase().wrapping_sub(b'a') as usize;
+        let y = bytes[1].wrapping_sub(b'1') as usize;
+        if x >= BOARD_SIZE || y >= BOARD_SIZE {
+            return Err(ParseSquareError(s.to_string()));
+        }
+        Ok(Square { x, y })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TranscriptError {
+    InvalidSquare { move_number: usize, text: String },
+    IllegalMove { move_number: usize, square: Square },
+    UnexpectedPass { move_number: usize },
+    GameAlreadyOver { move_number: usize },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranscriptError::InvalidSquare { move_number, text } => {
+                write!(f, "move {}: '{}' is not a square, expected a1 to h8", move_number, text)
+            }
+            TranscriptError::IllegalMove { move_number, square } => {
+                write!(f, "move {}: {} is not a legal move", move_number, square)
+            }
+            TranscriptError::UnexpectedPass { move_number } => {
+                write!(f, "move {}: pass while a move is possible", move_number)
+            }
+            TranscriptError::GameAlreadyOver { move_number } => {
+                write!(f, "move {}: the game is already over", move_number)
+            }
+        }
+    }
+}
+
+pub fn transcript(game: &Game) -> String {
+    game.moves.iter().map(|sq| sq.to_string()).collect()
+}
+
+// Replays a transcript from the starting position. Whitespace, '-' and ',' between moves are
+// ignored and the letters may be upper or lower case.
+pub fn game_from_transcript(text: &str) -> Result<Game, TranscriptError> {
+    play_transcript(init_game(), text)
+}
+
+fn play_transcript(mut game: Game, text: &str) -> Result<Game, TranscriptError> {
+    let characters: Vec<char> = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != ',')
+        .collect();
+    // whether the last move was followed by a forced pass that the transcript may spell out
+    let mut pass_happened = false;
+
+    for (index, pair) in characters.chunks(2).enumerate() {
+        let move_number = index + 1;
+        let text: String = pair.iter().collect();
+        if text.eq_ignore_ascii_case("pa") {
+            if !pass_happened {
+                return Err(TranscriptError::UnexpectedPass { move_number });
+            }
+            pass_happened = false;
+            continue;
+        }
+        let square: Square = text
+            .parse()
+            .map_err(|_| TranscriptError::InvalidSquare { move_number, text: text.clone() })?;
+        if game.board_essential.game_over {
+            return Err(TranscriptError::GameAlreadyOver { move_number });
+        }
+        if !legal_moves(&game.board_essential).contains(&square) {
+            return Err(TranscriptError::IllegalMove { move_number, square });
+        }
+        let white_moved = game.board_essential.white_turn;
+        do_move(square.x, square.y, &mut game);
+        pass_happened = !game.board_essential.game_over && game.board_essential.white_turn == white_moved;
+    }
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game_from_board_essentials, start_board_essentials, Colour};
+
+    #[test]
+    fn squares_round_trip() {
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let square = Square { x, y };
+                assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+            }
+        }
+        assert_eq!(Square { x: 5, y: 4 }.to_string(), "f5");
+        assert_eq!("H8".parse::<Square>(), Ok(Square { x: 7, y: 7 }));
+        assert!("i1".parse::<Square>().is_err());
+        assert!("a9".parse::<Square>().is_err());
+        assert!("a".parse::<Square>().is_err());
+    }
+
+    #[test]
+    fn transcript_round_trip() {
+        let text = "f5d6c3d3c4f4f6f3e6e7";
+        let game = game_from_transcript(text).unwrap();
+        assert_eq!(game.moves.len(), 10);
+        assert_eq!(transcript(&game), text);
+        assert_eq!(transcript(&game_from_transcript("F5 D6 C3-d3").unwrap()), "f5d6c3d3");
+    }
+
+    #[test]
+    fn transcripts_are_played_whoever_moves_first() {
+        for white_first in [true, false] {
+            let start = game_from_board_essentials(start_board_essentials(white_first));
+            let game = play_transcript(start, "f5d6c3d3c4").unwrap();
+            let first = if white_first { Colour::WHITE } else { Colour::BLACK };
+            assert_eq!(game.board_essential.board[5][4], first);
+            assert_eq!(game.board_essential.white_turn, !white_first);
+        }
+    }
+
+    #[test]
+    fn transcript_errors() {
+        assert_eq!(
+            game_from_transcript("f5a1").err(),
+            Some(TranscriptError::IllegalMove { move_number: 2, square: Square { x: 0, y: 0 } })
+        );
+        assert_eq!(
+            game_from_transcript("f5z9").err(),
+            Some(TranscriptError::InvalidSquare { move_number: 2, text: "z9".to_string() })
+        );
+        assert_eq!(
+            game_from_transcript("f5pa").err(),
+            Some(TranscriptError::UnexpectedPass { move_number: 2 })
+        );
+    }
+
+    #[test]
+    fn shortest_game_ends_and_rejects_further_moves() {
+        // one of the 9-move wipeouts
+        let game = game_from_transcript("c4c3c2b4a5f4g4c5d6").unwrap();
+        assert!(game.board_essential.game_over);
+        assert_eq!(
+            game_from_transcript("c4c3c2b4a5f4g4c5d6e6").err(),
+            Some(TranscriptError::GameAlreadyOver { move_number: 10 })
+        );
+    }
+}
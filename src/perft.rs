@@ -0,0 +1,140 @@
+use crate::position::PositionError;
+use crate::{do_move_essentials, init_board_essentials, BoardEssentials, Square, BOARD_SIZE};
+
+// Counts the leaf nodes of the game tree `depth` plies deep, used to verify move generation.
+// A forced pass counts as a ply of its own and a game that ends before `depth` counts as a leaf,
+// which is the convention of the published Othello perft numbers.
+pub fn perft(board_essential: &BoardEssentials, depth: usize) -> u64 {
+    if depth == 0 || board_essential.game_over {
+        return 1;
+    }
+    let mut nodes = 0;
+    for x in 0..BOARD_SIZE {
+        for y in 0..BOARD_SIZE {
+            if board_essential.possible_moves[x][y].is_empty() {
+                continue;
+            }
+            let mut clone = board_essential.clone();
+            do_move_essentials(x, y, &mut clone);
+            nodes += perft_after_move(board_essential.white_turn, &clone, depth - 1);
+        }
+    }
+    nodes
+}
+
+// do_move_essentials passes automatically, so a pass shows up as the same side being to move again.
+fn perft_after_move(mover_is_white: bool, board_essential: &BoardEssentials, depth: usize) -> u64 {
+    if depth == 0 || board_essential.game_over {
+        return 1;
+    }
+    if board_essential.white_turn == mover_is_white {
+        return perft(board_essential, depth - 1);
+    }
+    perft(board_essential, depth)
+}
+
+// The perft count below each legal root move, in square order.
+pub fn divide(board_essential: &BoardEssentials, depth: usize) -> Vec<(String, u64)> {
+    let mut result = vec![];
+    if depth == 0 {
+        return result;
+    }
+    for x in 0..BOARD_SIZE {
+        for y in 0..BOARD_SIZE {
+            if board_essential.possible_moves[x][y].is_empty() {
+                continue;
+            }
+            let mut clone = board_essential.clone();
+            do_move_essentials(x, y, &mut clone);
+            result.push((Square { x, y }.to_string(), perft_after_move(board_essential.white_turn, &clone, depth - 1)));
+        }
+    }
+    result
+}
+
+// Usage: othello perft <depth> [--divide] [--position "<position>"]
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let mut depth = None;
+    let mut show_divide = false;
+    let mut board_essential = init_board_essentials();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--divide" => show_divide = true,
+            "--position" => {
+                let position = iter.next().ok_or("missing value for --position")?;
+                board_essential = position.parse().map_err(|e: PositionError| e.to_string())?;
+            }
+            _ => match arg.parse::<usize>() {
+                Ok(d) if depth.is_none() => depth = Some(d),
+                _ => return Err(format!("unknown perft argument '{}'", arg)),
+            },
+        }
+    }
+    let depth = depth.ok_or("usage: othello perft <depth> [--divide] [--position \"<position>\"]")?;
+
+    let start = std::time::Instant::now();
+    let nodes = if show_divide {
+        let moves = divide(&board_essential, depth);
+        for (name, count) in moves.iter() {
+            println!("{}: {}", name, count);
+        }
+        moves.iter().map(|(_, count)| count).sum()
+    } else {
+        perft(&board_essential, depth)
+    };
+    let seconds = start.elapsed().as_secs_f64();
+    println!(
+        "perft({}) = {} in {:.3}s ({:.0} nodes/s)",
+        depth,
+        nodes,
+        seconds,
+        nodes as f64 / seconds.max(1e-9)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Published perft numbers from the standard starting position.
+    const START_PERFT: [u64; 9] = [1, 4, 12, 56, 244, 1396, 8200, 55092, 390216];
+
+    #[test]
+    fn perft_from_start_matches_published_numbers() {
+        let board_essential = init_board_essentials();
+        for (depth, expected) in START_PERFT.iter().enumerate().take(8) {
+            assert_eq!(perft(&board_essential, depth), *expected, "depth {}", depth);
+        }
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds, run with --release --ignored"]
+    fn perft_depth_8_to_10() {
+        let board_essential = init_board_essentials();
+        assert_eq!(perft(&board_essential, 8), START_PERFT[8]);
+        assert_eq!(perft(&board_essential, 9), 3_005_288);
+        // the first games end after 9 plies, so this also checks how finished games are counted
+        assert_eq!(perft(&board_essential, 10), 24_571_284);
+    }
+
+    #[test]
+    fn passes_count_as_a_ply() {
+        // black c1, white has to pass, black e1 ends the game
+        let board_essential: BoardEssentials = format!("XO-O{} X", "-".repeat(60)).parse().unwrap();
+        for depth in 1..5 {
+            assert_eq!(perft(&board_essential, depth), 1, "depth {}", depth);
+        }
+        assert_eq!(divide(&board_essential, 3), vec![("c1".to_string(), 1)]);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board_essential = init_board_essentials();
+        let moves = divide(&board_essential, 5);
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|(_, count)| *count == 1396 / 4));
+        assert_eq!(moves.iter().map(|(_, c)| c).sum::<u64>(), perft(&board_essential, 5));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{board_essentials_from_board, BoardEssentials, Colour, BOARD_SIZE};
+use std::fmt;
+use std::str::FromStr;
+
+// Positions as text in the style of OBF files and Edax: the 64 squares row by row from a1 to h8,
+// 'X' for black, 'O' for white and '-' for empty, then a space and the side to move, e.g.
+// "---------------------------OX------XO--------------------------- X".
+// When reading, '*' is accepted for black and '.' for empty, and anything after a ';' is ignored
+// so lines of OBF files (which append the best moves) can be read directly.
+
+#[derive(Debug, PartialEq)]
+pub enum PositionError {
+    WrongLength(usize),
+    InvalidSquare(char),
+    InvalidSideToMove(String),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::WrongLength(length) => {
+                write!(f, "a position has {} squares, found {}", BOARD_SIZE * BOARD_SIZE, length)
+            }
+            PositionError::InvalidSquare(c) => write!(f, "'{}' is not a square, expected X, O or -", c),
+            PositionError::InvalidSideToMove(side) => write!(f, "'{}' is not a side to move, expected X or O", side),
+        }
+    }
+}
+
+impl fmt::Display for BoardEssentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..BOARD_SIZE {
+            for column in self.board.iter() {
+                let c = match column[y] {
+                    Colour::BLACK => 'X',
+                    Colour::WHITE => 'O',
+                    Colour::EMPTY => '-',
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+        write!(f, " {}", if self.white_turn { 'O' } else { 'X' })
+    }
+}
+
+// The side to move is taken as given, unless it has to pass; see board_essentials_from_board.
+impl FromStr for BoardEssentials {
+    type Err = PositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let position = s.split(';').next().unwrap_or("");
+        let mut parts = position.split_whitespace();
+        let squares: Vec<char> = parts.next().unwrap_or("").chars().collect();
+        if squares.len() != BOARD_SIZE * BOARD_SIZE {
+            return Err(PositionError::WrongLength(squares.len()));
+        }
+        let mut board = [[Colour::EMPTY; BOARD_SIZE]; BOARD_SIZE];
+        for (i, c) in squares.iter().enumerate() {
+            board[i % BOARD_SIZE][i / BOARD_SIZE] = match c {
+                'X' | 'x' | '*' => Colour::BLACK,
+                'O' | 'o' => Colour::WHITE,
+                '-' | '.' => Colour::EMPTY,
+                _ => return Err(PositionError::InvalidSquare(*c)),
+            };
+        }
+        let white_turn = match parts.next() {
+            Some("X") | Some("x") | Some("*") => false,
+            Some("O") | Some("o") => true,
+            other => return Err(PositionError::InvalidSideToMove(other.unwrap_or("").to_string())),
+        };
+        Ok(board_essentials_from_board(board, white_turn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_board_essentials;
+
+    #[test]
+    fn start_position_round_trips() {
+        let start = init_board_essentials();
+        let text = start.to_string();
+        assert_eq!(text, "---------------------------XO------OX--------------------------- O");
+        let parsed: BoardEssentials = text.parse().unwrap();
+        assert_eq!(parsed.board, start.board);
+        assert_eq!(parsed.white_turn, start.white_turn);
+        assert_eq!(parsed.amount_of_stone, 4);
+        assert_eq!(parsed.possible_moves, start.possible_moves);
+    }
+
+    #[test]
+    fn reads_obf_lines() {
+        let parsed: BoardEssentials = "---------------------------OX------XO--------------------------- X; F5:+0;"
+            .parse()
+            .unwrap();
+        assert!(!parsed.white_turn);
+        assert_eq!(parsed.board[4][3], Colour::BLACK);
+        assert!(!parsed.possible_moves[5][4].is_empty());
+    }
+
+    #[test]
+    fn side_without_moves_passes() {
+        // white has no disc next to an empty square in line with a black one
+        let text = format!("XO{} O", "-".repeat(62));
+        let parsed: BoardEssentials = text.parse().unwrap();
+        assert!(!parsed.white_turn);
+        assert!(!parsed.game_over);
+        let text = format!("XX{} O", "-".repeat(62));
+        let parsed: BoardEssentials = text.parse().unwrap();
+        assert!(parsed.game_over);
+        assert_eq!(parsed.winner, Colour::BLACK);
+    }
+
+    #[test]
+    fn rejects_malformed_positions() {
+        assert_eq!("XO- X".parse::<BoardEssentials>().err(), Some(PositionError::WrongLength(3)));
+        assert_eq!(
+            format!("{}Z X", "-".repeat(63)).parse::<BoardEssentials>().err(),
+            Some(PositionError::InvalidSquare('Z'))
+        );
+        assert_eq!(
+            "-".repeat(64).parse::<BoardEssentials>().err(),
+            Some(PositionError::InvalidSideToMove(String::new()))
+        );
+    }
+}
@@ -0,0 +1,231 @@
+// Randomized conformance tests for the rules in main.rs. Every test plays random games, picking
+// moves with the generated numbers, and checks each position against a straightforward reference
+// implementation of the rules.
+use crate::*;
+use proptest::prelude::*;
+
+const DIRECTIONS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+fn colour_to_move(board_essential: &BoardEssentials) -> Colour {
+    if board_essential.white_turn {
+        Colour::WHITE
+    } else {
+        Colour::BLACK
+    }
+}
+
+fn opponent(colour: Colour) -> Colour {
+    match colour {
+        Colour::WHITE => Colour::BLACK,
+        Colour::BLACK => Colour::WHITE,
+        Colour::EMPTY => Colour::EMPTY,
+    }
+}
+
+fn reference_flips(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE], x: usize, y: usize, colour: Colour) -> Vec<(usize, usize)> {
+    let mut flips = vec![];
+    if board[x][y] != Colour::EMPTY {
+        return flips;
+    }
+    for (dx, dy) in DIRECTIONS {
+        let mut line = vec![];
+        let (mut cx, mut cy) = (x as isize + dx, y as isize + dy);
+        while (0..BOARD_SIZE as isize).contains(&cx) && (0..BOARD_SIZE as isize).contains(&cy) {
+            let square = board[cx as usize][cy as usize];
+            if square == opponent(colour) {
+                line.push((cx as usize, cy as usize));
+            } else {
+                if square == colour {
+                    flips.append(&mut line);
+                }
+                break;
+            }
+            cx += dx;
+            cy += dy;
+        }
+    }
+    flips.sort();
+    flips
+}
+
+fn reference_has_move(board: &[[Colour; BOARD_SIZE]; BOARD_SIZE], colour: Colour) -> bool {
+    (0..BOARD_SIZE).any(|x| (0..BOARD_SIZE).any(|y| !reference_flips(board, x, y, colour).is_empty()))
+}
+
+fn legal_moves(board_essential: &BoardEssentials) -> Vec<(usize, usize)> {
+    let mut moves = vec![];
+    for x in 0..BOARD_SIZE {
+        for y in 0..BOARD_SIZE {
+            if !board_essential.possible_moves[x][y].is_empty() {
+                moves.push((x, y));
+            }
+        }
+    }
+    moves
+}
+
+fn sorted_flips(flips: &[Square]) -> Vec<(usize, usize)> {
+    let mut flips: Vec<(usize, usize)> = flips.iter().map(|sq| (sq.x, sq.y)).collect();
+    flips.sort();
+    flips
+}
+
+// Plays the moves picked by `choices` and returns every position of the game, the start included.
+fn random_game(choices: &[usize]) -> Vec<BoardEssentials> {
+    let mut positions = vec![init_board_essentials()];
+    for choice in choices {
+        let current = positions.last().unwrap();
+        if current.game_over {
+            break;
+        }
+        let moves = legal_moves(current);
+        let (x, y) = moves[choice % moves.len()];
+        let mut next = current.clone();
+        do_move_essentials(x, y, &mut next);
+        positions.push(next);
+    }
+    positions
+}
+
+// One of the eight symmetries of the square, numbered 0..8.
+fn transform(symmetry: usize, x: usize, y: usize) -> (usize, usize) {
+    let n = BOARD_SIZE - 1;
+    let (x, y) = if symmetry & 4 != 0 { (y, x) } else { (x, y) };
+    let x = if symmetry & 1 != 0 { n - x } else { x };
+    let y = if symmetry & 2 != 0 { n - y } else { y };
+    (x, y)
+}
+
+fn choices() -> impl Strategy<Value = Vec<usize>> {
+    proptest::collection::vec(0..64usize, 0..64)
+}
+
+proptest! {
+    #[test]
+    fn flips_match_reference(choices in choices()) {
+        for position in random_game(&choices) {
+            if position.game_over {
+                continue;
+            }
+            let colour = colour_to_move(&position);
+            for x in 0..BOARD_SIZE {
+                for y in 0..BOARD_SIZE {
+                    prop_assert_eq!(
+                        sorted_flips(&position.possible_moves[x][y]),
+                        reference_flips(&position.board, x, y, colour),
+                        "square ({}, {})", x, y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn disc_counts_change_by_one_plus_flips(choices in choices(), pick in 0..64usize) {
+        for position in random_game(&choices) {
+            if position.game_over {
+                continue;
+            }
+            let moves = legal_moves(&position);
+            let (x, y) = moves[pick % moves.len()];
+            let flips = position.possible_moves[x][y].len();
+            let mut next = position.clone();
+            do_move_essentials(x, y, &mut next);
+
+            let (black_before, white_before) = count_stones(&position.board);
+            let (black_after, white_after) = count_stones(&next.board);
+            let (mover_gain, opponent_loss) = if position.white_turn {
+                (white_after - white_before, black_before - black_after)
+            } else {
+                (black_after - black_before, white_before - white_after)
+            };
+            prop_assert_eq!(mover_gain, 1 + flips);
+            prop_assert_eq!(opponent_loss, flips);
+            prop_assert_eq!(next.amount_of_stone, position.amount_of_stone + 1);
+            prop_assert_eq!(next.amount_of_stone, black_after + white_after);
+        }
+    }
+
+    #[test]
+    fn passes_only_without_legal_moves(choices in choices()) {
+        let positions = random_game(&choices);
+        for pair in positions.windows(2) {
+            let (before, after) = (&pair[0], &pair[1]);
+            if after.game_over {
+                continue;
+            }
+            let opponent_colour = opponent(colour_to_move(before));
+            let passed = after.white_turn == before.white_turn;
+            prop_assert_eq!(passed, !reference_has_move(&after.board, opponent_colour));
+            prop_assert!(!legal_moves(after).is_empty());
+        }
+    }
+
+    #[test]
+    fn game_over_iff_neither_side_can_move(choices in choices()) {
+        for position in random_game(&choices) {
+            let neither_can_move = !reference_has_move(&position.board, Colour::WHITE)
+                && !reference_has_move(&position.board, Colour::BLACK);
+            prop_assert_eq!(position.game_over, neither_can_move);
+            if position.game_over {
+                let (black, white) = count_stones(&position.board);
+                let expected = match black.cmp(&white) {
+                    std::cmp::Ordering::Greater => Colour::BLACK,
+                    std::cmp::Ordering::Less => Colour::WHITE,
+                    std::cmp::Ordering::Equal => Colour::EMPTY,
+                };
+                prop_assert_eq!(position.winner, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn undo_restores_prior_state(choices in choices()) {
+        let mut game = init_game();
+        let mut history = vec![];
+        for choice in choices.iter() {
+            if game.board_essential.game_over {
+                break;
+            }
+            let moves = legal_moves(&game.board_essential);
+            let (x, y) = moves[choice % moves.len()];
+            history.push(game.board_essential.clone());
+            do_move(x, y, &mut game);
+        }
+        while let Some(expected) = history.pop() {
+            undo(&mut game);
+            let restored = &game.board_essential;
+            prop_assert_eq!(restored.board, expected.board);
+            prop_assert_eq!(restored.white_turn, expected.white_turn);
+            prop_assert_eq!(&restored.possible_moves, &expected.possible_moves);
+            prop_assert_eq!(restored.amount_of_stone, expected.amount_of_stone);
+            prop_assert_eq!(restored.game_over, expected.game_over);
+        }
+    }
+
+    #[test]
+    fn symmetries_commute_with_move_generation(choices in choices(), symmetry in 0..8usize) {
+        for position in random_game(&choices) {
+            let mut transformed = position.clone();
+            for x in 0..BOARD_SIZE {
+                for y in 0..BOARD_SIZE {
+                    let (tx, ty) = transform(symmetry, x, y);
+                    transformed.board[tx][ty] = position.board[x][y];
+                }
+            }
+            let (moves, a_move_exists) = get_all_possible_moves(&transformed);
+            prop_assert_eq!(a_move_exists, !legal_moves(&position).is_empty());
+            for x in 0..BOARD_SIZE {
+                for y in 0..BOARD_SIZE {
+                    let (tx, ty) = transform(symmetry, x, y);
+                    let mut expected: Vec<(usize, usize)> = position.possible_moves[x][y]
+                        .iter()
+                        .map(|sq| transform(symmetry, sq.x, sq.y))
+                        .collect();
+                    expected.sort();
+                    prop_assert_eq!(sorted_flips(&moves[tx][ty]), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::controller::{Controller, Player};
+use crate::ggf::{parse_ggf, write_ggf, GgfRecord};
+use crate::notation::{game_from_transcript, transcript};
+use crate::settings::settings;
+use crate::{Game, GameInfo};
+use std::path::{Path, PathBuf};
+
+// Saving games to files and loading them again. Files ending in .ggf are written in GGF with the
+// players, the result and the variations; any other file gets the transcript of the game, which
+// every Othello program can read. Loading accepts both, whatever the file is called.
+// The game in progress is also autosaved after every move, so that it survives the program being
+// closed or crashing; the next start offers to resume it.
+
+// How the players are named in saved games, e.g. "othello level 5" for the AI searching 5 plies.
+const AI_NAME: &str = "othello level ";
+const HUMAN_NAME: &str = "human";
+
+pub fn player_name(player: Player, depth: usize) -> String {
+    match player {
+        Player::Human => HUMAN_NAME.to_string(),
+        Player::Ai => format!("{}{}", AI_NAME, depth),
+    }
+}
+
+fn parse_player_name(name: &str) -> Option<(Player, usize)> {
+    if name == HUMAN_NAME {
+        return Some((Player::Human, 0));
+    }
+    let depth = name.strip_prefix(AI_NAME)?.parse().ok().filter(|&depth| depth > 0)?;
+    Some((Player::Ai, depth))
+}
+
+// The players of a game saved here (black then white, with their search depths), or None for
+// games from elsewhere.
+pub fn players_from_info(info: &GameInfo) -> Option<[(Player, usize); 2]> {
+    let black = parse_player_name(info.black_player.as_deref()?)?;
+    let white = parse_player_name(info.white_player.as_deref()?)?;
+    Some([black, white])
+}
+
+fn is_ggf(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ggf"))
+}
+
+pub fn save_game(controller: &Controller, path: &Path) -> Result<(), String> {
+    let game = controller.record();
+    let text = if is_ggf(path) {
+        write_ggf(&GgfRecord::from_game(&game))
+    } else {
+        transcript(&game)
+    };
+    std::fs::write(path, text + "\n").map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+// Reads the first game of a GGF text or a transcript.
+pub fn parse_saved_game(text: &str) -> Result<Game, String> {
+    if text.trim_start().starts_with("(;") {
+        let mut records = parse_ggf(text).map_err(|e| e.to_string())?;
+        Ok(records.remove(0).game)
+    } else {
+        game_from_transcript(text.trim()).map_err(|e| e.to_string())
+    }
+}
+
+pub fn load_game(path: &Path) -> Result<Game, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    parse_saved_game(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Keeps the autosave file up to date with a game. Nothing is written until a move is played, so
+// merely starting the program keeps the last unfinished game, and a finished game deletes the file
+// as there is nothing left to resume.
+pub struct Autosave {
+    path: Option<PathBuf>,
+    // what the file was last brought up to date with
+    saved: String,
+}
+
+impl Autosave {
+    pub fn new(path: Option<&Path>, controller: &Controller) -> Autosave {
+        Autosave { path: path.map(Path::to_path_buf), saved: write_ggf(&GgfRecord::from_game(&controller.record())) }
+    }
+
+    // Autosaving to the autosave_file setting.
+    pub fn from_settings(controller: &Controller) -> Autosave {
+        Autosave::new(settings().autosave_file.as_deref(), controller)
+    }
+
+    // Saves the game if it changed since the last call. Errors are reported but do not stop the
+    // game.
+    pub fn update(&mut self, controller: &Controller) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let game = controller.record();
+        let text = write_ggf(&GgfRecord::from_game(&game));
+        if text == self.saved {
+            return;
+        }
+        self.saved = text;
+        let result = if game.moves.is_empty() {
+            Ok(())
+        } else if game.board_essential.game_over {
+            match std::fs::remove_file(path) {
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        } else {
+            // written next to it first, so that a crash while writing leaves the old file intact
+            let temporary = path.with_extension("tmp");
+            std::fs::write(&temporary, self.saved.clone() + "\n").and_then(|()| std::fs::rename(&temporary, path))
+        };
+        if let Err(error) = result {
+            eprintln!("Cannot autosave to {}: {}", path.display(), error);
+        }
+    }
+}
+
+// The game left unfinished in the autosave file, if there is one.
+pub fn unfinished_game(path: &Path) -> Option<Game> {
+    load_game(path).ok().filter(|game| !game.moves.is_empty() && !game.board_essential.game_over)
+}
+
+pub fn autosaved_game() -> Option<Game> {
+    unfinished_game(settings().autosave_file.as_deref()?)
+}
+
+// After a crash, tells where the game in progress was saved.
+pub fn report_autosave_on_panic() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        if let Some(path) = &settings().autosave_file {
+            if path.exists() {
+                eprintln!("The game in progress was autosaved to {}, --resume continues it.", path.display());
+            }
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_game;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("othello-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn ggf_files_keep_players_result_and_variations() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Ai], 4);
+        for square in ["c4", "c3", "c2"] {
+            controller.play(square.parse().unwrap()).unwrap();
+        }
+        controller.undo();
+        controller.play("e6".parse().unwrap()).unwrap();
+        controller.resign(false);
+        let path = temp_file("save.ggf");
+        save_game(&controller, &path).unwrap();
+        let game = load_game(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(transcript(&game), "c4c3e6");
+        assert_eq!(players_from_info(&game.info), Some([(Player::Human, 0), (Player::Ai, 4)]));
+        assert_eq!(game.info.result.as_deref(), Some("-64.000:r"));
+        assert_eq!(game.info.variations.as_deref(), Some("c4c3c2(e6)"));
+    }
+
+    #[test]
+    fn other_files_get_a_transcript() {
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        controller.play("c4".parse().unwrap()).unwrap();
+        let path = temp_file("save.txt");
+        save_game(&controller, &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "c4\n");
+        assert_eq!(transcript(&load_game(&path).unwrap()), "c4");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(parse_saved_game("c4a1").err().unwrap().contains("a1 is not a legal move"));
+        assert!(load_game(&temp_file("missing.ggf")).err().unwrap().starts_with("cannot read"));
+        let info = GameInfo {
+            black_player: Some("alice".to_string()),
+            white_player: Some(HUMAN_NAME.to_string()),
+            ..GameInfo::default()
+        };
+        assert_eq!(players_from_info(&info), None);
+    }
+
+    #[test]
+    fn autosave_follows_the_game_until_it_ends() {
+        let path = temp_file("autosave.ggf");
+        std::fs::write(&path, "c4\n").unwrap();
+        let mut controller = Controller::new(init_game(), [Player::Human, Player::Human], 1);
+        let mut autosave = Autosave::new(Some(&path), &controller);
+        autosave.update(&controller);
+        assert_eq!(transcript(&unfinished_game(&path).unwrap()), "c4");
+
+        controller.play("e6".parse().unwrap()).unwrap();
+        autosave.update(&controller);
+        assert_eq!(transcript(&unfinished_game(&path).unwrap()), "e6");
+        controller.play("f4".parse().unwrap()).unwrap();
+        autosave.update(&controller);
+        assert_eq!(transcript(&unfinished_game(&path).unwrap()), "e6f4");
+        controller.resign(true);
+        autosave.update(&controller);
+        assert!(!path.exists());
+        assert!(unfinished_game(&path).is_none());
+    }
+}
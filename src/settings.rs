@@ -0,0 +1,359 @@
+use crate::book::{load_book, Book};
+use crate::controller::Player;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+// Settings that used to be compile-time constants. They are read once at start-up, from an
+// optional TOML config file and then from command-line flags, which take precedence:
+//
+//   first = "white"          # side to move first: black or white          --first
+//   ai = "black"             # the AI's side: black, white, both or none    --ai
+//   depth = 5                # search depth of the AI                       --depth
+//   window_size = 500        # height of the window (and board) in pixels   --window-size
+//   time_control = "5+3"     # minutes per side + seconds per move          --time
+//   threads = 4              # threads for tournaments                      --threads
+//   hash_mb = 16             # transposition table of the endgame solver    --hash
+//   book = "book.txt"        # opening book, one transcript per line       --book
+//   theme = "classic"        # colours of the window: classic, green, dark  --theme
+//   save_file = "game.ggf"   # where the window saves (S) and loads (L)     --save
+//   autosave_file = "a.ggf"  # the game in progress, saved after every move  --autosave
+//                            # ("off" turns autosaving off)
+//
+// The file is given with --config, otherwise othello.toml in the working directory is used if it
+// exists.
+
+const DEFAULT_CONFIG_FILE: &str = "othello.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Theme {
+    Classic,
+    Green,
+    Dark,
+}
+
+pub struct ThemeColours {
+    pub background: [f32; 4],
+    pub grid: [f32; 4],
+    pub last_move: [f32; 4],
+    pub flipped: [f32; 4],
+    pub legal_move: [f32; 4],
+    pub hint: [f32; 4],
+}
+
+impl Theme {
+    pub fn colours(&self) -> ThemeColours {
+        match self {
+            Theme::Classic => ThemeColours {
+                background: [0.5, 0.5, 0.5, 1.0],
+                grid: [0.0, 0.0, 0.0, 1.0],
+                last_move: [0.0, 0.0, 0.8, 1.0],
+                flipped: [0.5, 0.5, 1.0, 1.0],
+                legal_move: [0.0, 0.5, 0.0, 1.0],
+                hint: [1.0, 0.8, 0.0, 1.0],
+            },
+            Theme::Green => ThemeColours {
+                background: [0.0, 0.45, 0.2, 1.0],
+                grid: [0.0, 0.2, 0.05, 1.0],
+                last_move: [0.8, 0.6, 0.0, 1.0],
+                flipped: [0.3, 0.6, 0.35, 1.0],
+                legal_move: [0.9, 0.9, 0.4, 1.0],
+                hint: [1.0, 0.45, 0.2, 1.0],
+            },
+            Theme::Dark => ThemeColours {
+                background: [0.15, 0.15, 0.18, 1.0],
+                grid: [0.4, 0.4, 0.45, 1.0],
+                last_move: [0.5, 0.2, 0.2, 1.0],
+                flipped: [0.3, 0.25, 0.3, 1.0],
+                legal_move: [0.3, 0.7, 0.9, 1.0],
+                hint: [0.95, 0.75, 0.25, 1.0],
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+pub struct Settings {
+    pub white_is_starting: bool,
+    // who plays black and white
+    pub players: [Player; 2],
+    pub depth: usize,
+    pub window_size: u32,
+    pub time_control: Option<TimeControl>,
+    pub threads: usize,
+    pub hash_mb: usize,
+    // the opening book is loaded after the settings are set, see book()
+    pub book: Option<PathBuf>,
+    pub theme: Theme,
+    pub save_file: PathBuf,
+    // None when autosaving is off
+    pub autosave_file: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            white_is_starting: true,
+            players: [Player::Ai, Player::Human],
+            depth: 5,
+            window_size: 500,
+            time_control: None,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            hash_mb: 16,
+            book: None,
+            theme: Theme::Classic,
+            save_file: PathBuf::from("othello-game.ggf"),
+            autosave_file: Some(PathBuf::from("othello-autosave.ggf")),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static BOOK: OnceLock<Book> = OnceLock::new();
+
+// The settings in effect; the defaults until init_from_args has run (as in the tests).
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+// The opening book given with the book setting, if any.
+pub fn book() -> Option<&'static Book> {
+    BOOK.get()
+}
+
+// The settings as written in the config file or given as flags, before they are checked.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSettings {
+    first: Option<String>,
+    ai: Option<String>,
+    depth: Option<i64>,
+    window_size: Option<i64>,
+    time_control: Option<String>,
+    threads: Option<i64>,
+    hash_mb: Option<i64>,
+    book: Option<String>,
+    theme: Option<String>,
+    save_file: Option<String>,
+    autosave_file: Option<String>,
+}
+
+fn parse_config(text: &str, path: &Path) -> Result<RawSettings, String> {
+    toml::from_str(text).map_err(|e| format!("{}: {}", path.display(), e.message().trim_end()))
+}
+
+fn parse_time_control(value: &str) -> Option<TimeControl> {
+    let (minutes, increment) = value.split_once('+').unwrap_or((value, "0"));
+    let minutes: f64 = minutes.trim().parse().ok()?;
+    let increment: f64 = increment.trim().parse().ok()?;
+    if !(minutes > 0.0 && increment >= 0.0 && minutes.is_finite() && increment.is_finite()) {
+        return None;
+    }
+    Some(TimeControl { base: Duration::from_secs_f64(minutes * 60.0), increment: Duration::from_secs_f64(increment) })
+}
+
+fn parse_number(flag: &str, text: &str) -> Result<i64, String> {
+    text.parse().map_err(|_| format!("{} expects a number, got '{}'", flag, text))
+}
+
+fn in_range(name: &str, value: i64, min: i64, max: i64) -> Result<i64, String> {
+    if value < min || value > max {
+        return Err(format!("{} must be between {} and {}, got {}", name, min, max, value));
+    }
+    Ok(value)
+}
+
+fn validate(raw: RawSettings) -> Result<Settings, String> {
+    let mut settings = Settings::default();
+    if let Some(first) = raw.first {
+        settings.white_is_starting = match first.as_str() {
+            "black" => false,
+            "white" => true,
+            _ => return Err(format!("first must be black or white, got '{}'", first)),
+        };
+    }
+    if let Some(ai) = raw.ai {
+        settings.players = match ai.as_str() {
+            "black" => [Player::Ai, Player::Human],
+            "white" => [Player::Human, Player::Ai],
+            "both" => [Player::Ai, Player::Ai],
+            "none" => [Player::Human, Player::Human],
+            _ => return Err(format!("ai must be black, white, both or none, got '{}'", ai)),
+        };
+    }
+    if let Some(depth) = raw.depth {
+        settings.depth = in_range("depth", depth, 1, 60)? as usize;
+    }
+    if let Some(size) = raw.window_size {
+        settings.window_size = in_range("window_size", size, 100, 4000)? as u32;
+    }
+    if let Some(time_control) = raw.time_control {
+        settings.time_control = Some(parse_time_control(&time_control).ok_or(format!(
+            "time_control must be minutes per side and optionally + seconds per move, e.g. \"5+3\", got '{}'",
+            time_control
+        ))?);
+    }
+    if let Some(threads) = raw.threads {
+        settings.threads = in_range("threads", threads, 1, 1024)? as usize;
+    }
+    if let Some(hash_mb) = raw.hash_mb {
+        settings.hash_mb = in_range("hash_mb", hash_mb, 1, 4096)? as usize;
+    }
+    if let Some(path) = raw.book {
+        settings.book = Some(PathBuf::from(path));
+    }
+    if let Some(theme) = raw.theme {
+        settings.theme = match theme.as_str() {
+            "classic" => Theme::Classic,
+            "green" => Theme::Green,
+            "dark" => Theme::Dark,
+            _ => return Err(format!("theme must be classic, green or dark, got '{}'", theme)),
+        };
+    }
+    if let Some(path) = raw.save_file {
+        settings.save_file = PathBuf::from(path);
+    }
+    if let Some(path) = raw.autosave_file {
+        settings.autosave_file = (path != "off").then(|| PathBuf::from(path));
+    }
+    Ok(settings)
+}
+
+// Reads the settings from the config file and the flags in `args` before the subcommand, and
+// returns the remaining arguments: the subcommand with its own arguments, or the window's flags.
+fn parse_args(args: &[String]) -> Result<(Settings, Vec<String>), String> {
+    let mut config_path: Option<PathBuf> = None;
+    let mut flags = RawSettings::default();
+    let mut rest = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        // the arguments after a subcommand are its own, e.g. "ffo --first 40"
+        if rest.is_empty() && !arg.starts_with("--") {
+            rest.push(arg.clone());
+            rest.extend(iter.cloned());
+            break;
+        }
+        let mut value = || iter.next().cloned().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--config" => config_path = Some(PathBuf::from(value()?)),
+            "--first" => flags.first = Some(value()?),
+            "--ai" => flags.ai = Some(value()?),
+            "--depth" => flags.depth = Some(parse_number(arg, &value()?)?),
+            "--window-size" => flags.window_size = Some(parse_number(arg, &value()?)?),
+            "--time" => flags.time_control = Some(value()?),
+            "--threads" => flags.threads = Some(parse_number(arg, &value()?)?),
+            "--hash" => flags.hash_mb = Some(parse_number(arg, &value()?)?),
+            "--book" => flags.book = Some(value()?),
+            "--theme" => flags.theme = Some(value()?),
+            "--save" => flags.save_file = Some(value()?),
+            "--autosave" => flags.autosave_file = Some(value()?),
+            _ => rest.push(arg.clone()),
+        }
+    }
+
+    let mut raw = RawSettings::default();
+    let path = config_path.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE));
+    if config_path.is_some() || path.exists() {
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        raw = parse_config(&text, &path)?;
+    }
+    // flags override the file
+    raw = RawSettings {
+        first: flags.first.or(raw.first),
+        ai: flags.ai.or(raw.ai),
+        depth: flags.depth.or(raw.depth),
+        window_size: flags.window_size.or(raw.window_size),
+        time_control: flags.time_control.or(raw.time_control),
+        threads: flags.threads.or(raw.threads),
+        hash_mb: flags.hash_mb.or(raw.hash_mb),
+        book: flags.book.or(raw.book),
+        theme: flags.theme.or(raw.theme),
+        save_file: flags.save_file.or(raw.save_file),
+        autosave_file: flags.autosave_file.or(raw.autosave_file),
+    };
+    Ok((validate(raw)?, rest))
+}
+
+pub fn init_from_args(args: &[String]) -> Result<Vec<String>, String> {
+    let (settings, rest) = parse_args(args)?;
+    if SETTINGS.set(settings).is_err() {
+        panic!("settings are initialised once");
+    }
+    // reading the book plays its lines from the starting position, which needs the settings
+    if let Some(path) = &SETTINGS.get().unwrap().book {
+        let _ = BOOK.set(load_book(path)?);
+    }
+    Ok(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn flags_are_taken_out_of_the_arguments() {
+        let (settings, rest) = parse_args(&args("--depth 7 --ai white --time 2+1 --theme dark play --black human")).unwrap();
+        assert_eq!(rest, args("play --black human"));
+        assert_eq!(settings.depth, 7);
+        assert_eq!(settings.players, [Player::Human, Player::Ai]);
+        assert_eq!(settings.theme, Theme::Dark);
+        assert_eq!(
+            settings.time_control,
+            Some(TimeControl { base: Duration::from_secs(120), increment: Duration::from_secs(1) })
+        );
+        assert_eq!(settings.autosave_file, Some(PathBuf::from("othello-autosave.ggf")));
+        let (settings, _) = parse_args(&args("--autosave off")).unwrap();
+        assert_eq!(settings.autosave_file, None);
+        let (settings, rest) = parse_args(&args("--moves f5d6 --depth 3")).unwrap();
+        assert_eq!(rest, args("--moves f5d6"));
+        assert_eq!(settings.depth, 3);
+    }
+
+    #[test]
+    fn flags_after_the_subcommand_belong_to_it() {
+        let (settings, rest) = parse_args(&args("--first black ffo --first 40 --last 40")).unwrap();
+        assert_eq!(rest, args("ffo --first 40 --last 40"));
+        assert!(!settings.white_is_starting);
+        let (settings, rest) = parse_args(&args("play --depth 7")).unwrap();
+        assert_eq!(rest, args("play --depth 7"));
+        assert_ne!(settings.depth, 7);
+    }
+
+    #[test]
+    fn config_file_is_read_and_flags_win() {
+        let path = std::env::temp_dir().join(format!("othello-settings-{}.toml", std::process::id()));
+        std::fs::write(&path, "first = \"black\"\ndepth = 3\nwindow_size = 800\n").unwrap();
+        let config = path.to_str().unwrap();
+        let (settings, _) = parse_args(&args(&format!("--config {} --depth 4", config))).unwrap();
+        assert!(!settings.white_is_starting);
+        assert_eq!(settings.depth, 4);
+        assert_eq!(settings.window_size, 800);
+
+        std::fs::write(&path, "dept = 3\n").unwrap();
+        let error = parse_args(&args(&format!("--config {}", config))).err().unwrap();
+        assert!(error.contains("unknown field `dept`"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_values_are_explained() {
+        let error = |text: &str| parse_args(&args(text)).err().unwrap();
+        assert_eq!(error("--depth 0"), "depth must be between 1 and 60, got 0");
+        assert_eq!(error("--depth deep"), "--depth expects a number, got 'deep'");
+        assert_eq!(error("--ai red"), "ai must be black, white, both or none, got 'red'");
+        assert!(error("--time 5+x").starts_with("time_control must be"));
+        assert!(error("--config /nonexistent/othello.toml").starts_with("cannot read"));
+        assert!(error("--theme").starts_with("missing value"));
+    }
+}
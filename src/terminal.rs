@@ -0,0 +1,280 @@
+use crate::controller::{default_players, Controller, Player};
+use crate::savegame::{autosaved_game, players_from_info, Autosave};
+use crate::settings::settings;
+use crate::{count_stones, legal_moves, notation, start_game_from_args, Colour, Game, Square, BOARD_SIZE};
+use std::io::{self, BufRead, Write};
+
+// Playing in the terminal, for when there is no window (over SSH or in CI). The board is printed
+// as text after every move and the human players type their moves as squares like "f5".
+
+fn parse_player(flag: &str, value: Option<&String>) -> Result<Player, String> {
+    match value.map(String::as_str) {
+        Some("human") => Ok(Player::Human),
+        Some("ai") => Ok(Player::Ai),
+        Some(other) => Err(format!("{} expects human or ai, got '{}'", flag, other)),
+        None => Err(format!("missing value for {}", flag)),
+    }
+}
+
+pub fn colour_name(white: bool) -> &'static str {
+    if white {
+        "White"
+    } else {
+        "Black"
+    }
+}
+
+// Columns a-h along the top and rows 1-8 down the side. Black is X, white is O, the last move is
+// in brackets and the moves of a human player to move are marked with a dot.
+pub fn render(game: &Game, show_moves: bool) -> String {
+    let mut text = String::from("  ");
+    for x in 0..BOARD_SIZE {
+        text.push_str(&format!(" {} ", (b'a' + x as u8) as char));
+    }
+    let last = game.moves.last();
+    for y in 0..BOARD_SIZE {
+        text.push_str(&format!("\n{} ", y + 1));
+        for x in 0..BOARD_SIZE {
+            let c = match game.board_essential.board[x][y] {
+                Colour::BLACK => 'X',
+                Colour::WHITE => 'O',
+                Colour::EMPTY if show_moves && !game.board_essential.possible_moves[x][y].is_empty() => '.',
+                Colour::EMPTY => '-',
+            };
+            if last == Some(&Square { x, y }) {
+                text.push_str(&format!("[{}]", c));
+            } else {
+                text.push_str(&format!(" {} ", c));
+            }
+        }
+    }
+    let (black, white) = count_stones(&game.board_essential.board);
+    text.push_str(&format!("\nBlack (X) {}  White (O) {}", black, white));
+    text
+}
+
+pub fn final_score(game: &Game) -> String {
+    let (black, white) = count_stones(&game.board_essential.board);
+    let result = match game.board_essential.winner {
+        Colour::BLACK => "black wins",
+        Colour::WHITE => "white wins",
+        Colour::EMPTY => "a draw",
+    };
+    format!("Game over: black {} - white {}, {}.\nTranscript: {}", black, white, result, notation::transcript(game))
+}
+
+// Plays the game to the end, reading the human moves from `input`. Returns early if the input ends
+// or a player types "quit".
+pub fn play(
+    controller: &mut Controller,
+    autosave: &mut Autosave,
+    input: impl BufRead,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut lines = input.lines();
+    let mut show_board = true;
+    while !controller.game.board_essential.game_over {
+        autosave.update(controller);
+        let white = controller.game.board_essential.white_turn;
+        let human = controller.human_to_move();
+        if show_board {
+            writeln!(out, "\n{}", render(&controller.game, human))?;
+        }
+        show_board = true;
+
+        let moves_before = controller.game.moves.len();
+        let square = if human {
+            write!(out, "{} to move: ", colour_name(white))?;
+            out.flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => return Ok(()),
+            };
+            match line.trim() {
+                "quit" => return Ok(()),
+                "undo" => {
+                    // take back the AI replies as well, back to this player's previous move
+                    if !controller.undo_to_human() {
+                        writeln!(out, "Nothing to undo.")?;
+                        show_board = false;
+                    }
+                    continue;
+                }
+                "moves" => {
+                    let moves: Vec<String> =
+                        legal_moves(&controller.game.board_essential).iter().map(|sq| sq.to_string()).collect();
+                    writeln!(out, "Legal moves: {}", moves.join(" "))?;
+                    show_board = false;
+                    continue;
+                }
+                text => match text.parse::<Square>() {
+                    Ok(square) => {
+                        if let Err(error) = controller.play(square) {
+                            writeln!(out, "{}, type \"moves\" to list them.", error)?;
+                            show_board = false;
+                        }
+                        None
+                    }
+                    Err(_) => {
+                        writeln!(out, "Type a square like f5, \"moves\", \"undo\" or \"quit\".")?;
+                        show_board = false;
+                        continue;
+                    }
+                },
+            }
+        } else {
+            controller.ai_move()
+        };
+        if let Some(square) = square {
+            writeln!(out, "{} (AI) plays {}", colour_name(white), square)?;
+        }
+
+        let game = &controller.game;
+        let moved = game.moves.len() > moves_before;
+        if moved && !game.board_essential.game_over && game.board_essential.white_turn == white {
+            writeln!(out, "{} has no legal move and passes.", colour_name(!white))?;
+        }
+    }
+    autosave.update(controller);
+    writeln!(out, "\n{}", render(&controller.game, false))?;
+    writeln!(out, "{}", final_score(&controller.game))
+}
+
+// The options shared by the terminal front-ends: --black human|ai, --white human|ai and the start
+// options of the window (--moves, --position, --ggf, --load, --resume). A game saved here is
+// continued with its players unless they are given.
+pub fn controller_from_args(args: &[String]) -> Result<Controller, String> {
+    let mut players: [Option<Player>; 2] = [None, None];
+    let mut start_args = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--black" => players[0] = Some(parse_player(arg, iter.next())?),
+            "--white" => players[1] = Some(parse_player(arg, iter.next())?),
+            _ => start_args.push(arg.clone()),
+        }
+    }
+    let game = start_game_from_args(&start_args)?;
+    let saved = players_from_info(&game.info);
+    let mut controller = Controller::new(game, default_players(), settings().depth);
+    for (i, player) in players.into_iter().enumerate() {
+        match (player, saved) {
+            (Some(player), _) => controller.players[i] = player,
+            (None, Some(saved)) => {
+                controller.players[i] = saved[i].0;
+                if saved[i].0 == Player::Ai {
+                    controller.ai_depths[i] = saved[i].1;
+                }
+            }
+            (None, None) => (),
+        }
+    }
+    Ok(controller)
+}
+
+// When a game is started afresh while another was left unfinished, asks whether to resume that
+// one, as autosaving the new game would overwrite it. Returns the arguments to start with, which
+// get --resume if the answer is yes.
+pub fn ask_to_resume(
+    args: &[String],
+    unfinished: bool,
+    mut input: impl BufRead,
+    out: &mut impl Write,
+) -> Result<Vec<String>, String> {
+    let fresh = !args.iter().any(|arg| ["--moves", "--position", "--ggf", "--load", "--resume"].contains(&arg.as_str()));
+    if !fresh || !unfinished {
+        return Ok(args.to_vec());
+    }
+    loop {
+        write!(out, "An unfinished game was autosaved. Resume it (a new game replaces it)? [y/n] ")
+            .and_then(|()| out.flush())
+            .map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Err("no answer whether to resume the unfinished game; start with --resume or --autosave off".to_string());
+        }
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok([args, &["--resume".to_string()]].concat()),
+            "n" | "no" => return Ok(args.to_vec()),
+            _ => (),
+        }
+    }
+}
+
+// Usage: othello play [--black human|ai] [--white human|ai] [--moves|--position|--ggf|--load ...|--resume]
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let args = ask_to_resume(args, autosaved_game().is_some(), &mut input, &mut io::stdout())?;
+    let mut controller = controller_from_args(&args)?;
+    let mut autosave = Autosave::from_settings(&controller);
+    play(&mut controller, &mut autosave, input, &mut io::stdout().lock()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{do_move, init_game};
+
+    fn run(game: Game, players: [Player; 2], input: &str) -> String {
+        let mut out = vec![];
+        let mut controller = Controller::new(game, players, 1);
+        let mut autosave = Autosave::new(None, &controller);
+        play(&mut controller, &mut autosave, input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn renders_coordinates_last_move_and_legal_moves() {
+        let mut game = init_game();
+        do_move(2, 3, &mut game);
+        let lines: Vec<String> = render(&game, true).lines().map(str::to_string).collect();
+        assert_eq!(lines[0], "   a  b  c  d  e  f  g  h ");
+        assert_eq!(lines[3], "3  -  -  .  -  .  -  -  - ");
+        assert_eq!(lines[4], "4  -  - [O] O  O  -  -  - ");
+        assert_eq!(lines[9], "Black (X) 1  White (O) 4");
+    }
+
+    #[test]
+    fn humans_move_undo_and_get_told_about_mistakes() {
+        let output = run(init_game(), [Player::Human, Player::Human], "a1\nhello\nc4\nundo\nc4\nmoves\nquit\n");
+        assert!(output.contains("a1 is not a legal move"));
+        assert!(output.contains("Type a square like f5"));
+        assert!(output.contains("Legal moves: c3 c5 e3"));
+        assert!(!output.contains("Game over"));
+    }
+
+    #[test]
+    fn announces_passes_and_the_final_score() {
+        // after a1 white's only disc is on b8, out of reach, so white passes and c8 ends the game
+        let position = format!("-OX{}XO------ X", "-".repeat(53));
+        let game = crate::game_from_board_essentials(position.parse().unwrap());
+        let output = run(game, [Player::Human, Player::Ai], "a1\nc8\n");
+        assert!(output.contains("White has no legal move and passes."), "{}", output);
+        assert!(output.contains("Game over: black 6 - white 0, black wins."), "{}", output);
+    }
+
+    #[test]
+    fn asks_before_a_new_game_replaces_the_unfinished_one() {
+        let ask = |args: &str, unfinished: bool, input: &str| {
+            let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+            let mut out = vec![];
+            let result = ask_to_resume(&args, unfinished, input.as_bytes(), &mut out).map(|args| args.join(" "));
+            (result, String::from_utf8(out).unwrap())
+        };
+        assert_eq!(ask("--black ai", false, ""), (Ok("--black ai".to_string()), String::new()));
+        assert_eq!(ask("--moves f5", true, "").0, Ok("--moves f5".to_string()));
+        let (args, out) = ask("--black ai", true, "maybe\ny\n");
+        assert_eq!(args, Ok("--black ai --resume".to_string()));
+        assert_eq!(out.matches("Resume it").count(), 2);
+        assert_eq!(ask("", true, "no\n").0, Ok(String::new()));
+        assert!(ask("", true, "").0.is_err());
+    }
+
+    #[test]
+    fn ai_plays_against_itself_to_the_end() {
+        let output = run(init_game(), [Player::Ai, Player::Ai], "");
+        assert!(output.contains("White (AI) plays") && output.contains("Black (AI) plays"));
+        assert!(output.contains("Game over: black"), "{}", output);
+    }
+}
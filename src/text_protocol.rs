@@ -0,0 +1,245 @@
+use crate::minmax::{best_move_and_value, evaluate_game, node_count, reset_node_count};
+use crate::{
+    count_stones, do_move, game_from_board_essentials, init_game, legal_moves, undo, BoardEssentials,
+    Colour, Game, Square, BOARD_SIZE,
+};
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+// A plain line-based interface for driving the engine from scripts. Every command is answered by
+// exactly one line starting with "ok" or "error <message>"; only show prints lines before it.
+//   newgame                 -> ok
+//   position <obf>          -> ok                          e.g. "position ---...--- X"
+//   play <square>           -> ok | ok pass | ok gameover <black discs> <white discs>
+//   undo                    -> ok
+//   legal                   -> ok <square> <square> ...
+//   go depth <n>            -> ok <square> depth <n> eval <eval> nodes <nodes> time <ms>
+//   go movetime <ms>           (the deepest search finished within the time)
+//   eval                    -> ok <eval>
+//   show                    -> the board, then ok <position>
+//   quit                    -> ok
+// "ok pass" means the opponent had to pass and the same side is to move again. Evaluations are
+// for the side to move; go does not play the move it finds.
+
+// A search is only started if it is expected to finish in time, assuming each ply takes this many
+// times longer than the one before.
+const BRANCHING_ESTIMATE: u32 = 6;
+
+fn side_eval(value: isize, white_to_move: bool) -> isize {
+    let white_eval = value.clamp(-isize::MAX, isize::MAX);
+    if white_to_move {
+        white_eval
+    } else {
+        -white_eval
+    }
+}
+
+struct SearchResult {
+    square: Square,
+    depth: usize,
+    eval: isize,
+    nodes: u64,
+    time: Duration,
+}
+
+fn search(board_essential: &BoardEssentials, depth: usize) -> SearchResult {
+    let start = Instant::now();
+    reset_node_count();
+    let (square, value) = best_move_and_value(board_essential, depth).expect("the game is not over");
+    let eval = side_eval(value, board_essential.white_turn);
+    SearchResult { square, depth, eval, nodes: node_count(), time: start.elapsed() }
+}
+
+// Searches one ply deeper at a time until the next search would likely run past `movetime`.
+fn search_for(board_essential: &BoardEssentials, movetime: Duration) -> SearchResult {
+    let start = Instant::now();
+    let max_depth = BOARD_SIZE * BOARD_SIZE - board_essential.amount_of_stone;
+    let mut result = search(board_essential, 1);
+    let mut nodes = result.nodes;
+    while result.depth < max_depth && start.elapsed() + result.time * BRANCHING_ESTIMATE <= movetime {
+        result = search(board_essential, result.depth + 1);
+        nodes += result.nodes;
+    }
+    SearchResult { nodes, time: start.elapsed(), ..result }
+}
+
+fn show(board_essential: &BoardEssentials) -> String {
+    let mut text = String::from(" ");
+    for x in 0..BOARD_SIZE {
+        text.push_str(&format!(" {}", (b'a' + x as u8) as char));
+    }
+    for y in 0..BOARD_SIZE {
+        text.push_str(&format!("\n{}", y + 1));
+        for x in 0..BOARD_SIZE {
+            let c = match board_essential.board[x][y] {
+                Colour::BLACK => 'X',
+                Colour::WHITE => 'O',
+                Colour::EMPTY if !board_essential.possible_moves[x][y].is_empty() => '.',
+                Colour::EMPTY => '-',
+            };
+            text.push_str(&format!(" {}", c));
+        }
+    }
+    text
+}
+
+fn handle(game: &mut Game, line: &str) -> Result<String, String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or("");
+    let arguments: Vec<&str> = words.collect();
+    let board_essential = &game.board_essential;
+    match command {
+        "newgame" => {
+            *game = init_game();
+            Ok(String::new())
+        }
+        "position" => {
+            let position: BoardEssentials = arguments.join(" ").parse().map_err(|e| format!("{}", e))?;
+            *game = game_from_board_essentials(position);
+            Ok(String::new())
+        }
+        "play" => {
+            let square: Square = arguments.first().unwrap_or(&"").parse().map_err(|e| format!("{}", e))?;
+            if board_essential.game_over {
+                return Err("the game is over".to_string());
+            }
+            if !legal_moves(board_essential).contains(&square) {
+                return Err(format!("{} is not a legal move", square));
+            }
+            let white_moved = board_essential.white_turn;
+            do_move(square.x, square.y, game);
+            if game.board_essential.game_over {
+                let (black, white) = count_stones(&game.board_essential.board);
+                Ok(format!("gameover {} {}", black, white))
+            } else if game.board_essential.white_turn == white_moved {
+                Ok("pass".to_string())
+            } else {
+                Ok(String::new())
+            }
+        }
+        "undo" => {
+            if game.prev_boards.is_empty() {
+                return Err("nothing to undo".to_string());
+            }
+            undo(game);
+            Ok(String::new())
+        }
+        "legal" => Ok(legal_moves(board_essential).iter().map(|sq| sq.to_string()).collect::<Vec<_>>().join(" ")),
+        "go" => {
+            if board_essential.game_over {
+                return Err("the game is over".to_string());
+            }
+            let value = arguments.get(1).and_then(|value| value.parse::<u64>().ok());
+            let result = match (arguments.first(), value) {
+                (Some(&"depth"), Some(depth)) if depth > 0 => search(board_essential, depth as usize),
+                (Some(&"movetime"), Some(ms)) => search_for(board_essential, Duration::from_millis(ms)),
+                _ => return Err("expected go depth <n> or go movetime <ms>".to_string()),
+            };
+            Ok(format!(
+                "{} depth {} eval {} nodes {} time {}",
+                result.square,
+                result.depth,
+                result.eval,
+                result.nodes,
+                result.time.as_millis()
+            ))
+        }
+        "eval" => Ok(side_eval(evaluate_game(board_essential), board_essential.white_turn).to_string()),
+        "show" => Ok(board_essential.to_string()),
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+// Runs a session until "quit" or the end of the input.
+pub fn run_session(input: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    let mut game = init_game();
+    for line in input.lines() {
+        let line = line?;
+        let command = line.split_whitespace().next().unwrap_or("");
+        match command {
+            "" => continue,
+            "quit" => {
+                writeln!(out, "ok")?;
+                break;
+            }
+            "show" => writeln!(out, "{}", show(&game.board_essential))?,
+            _ => (),
+        }
+        match handle(&mut game, &line) {
+            Ok(answer) if answer.is_empty() => writeln!(out, "ok")?,
+            Ok(answer) => writeln!(out, "ok {}", answer)?,
+            Err(error) => writeln!(out, "error {}", error)?,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// Usage: othello text
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    if let Some(arg) = args.first() {
+        return Err(format!("unknown text argument '{}'", arg));
+    }
+    let stdin = io::stdin();
+    run_session(stdin.lock(), &mut io::stdout().lock()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(script: &str) -> Vec<String> {
+        let mut out = vec![];
+        run_session(script.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn plays_and_undoes_moves() {
+        let lines = session("legal\nplay f5\nplay f5\nlegal\nundo\nlegal\nundo\nquit\nlegal\n");
+        assert_eq!(
+            lines,
+            vec![
+                "ok c4 d3 e6 f5",
+                "ok",
+                "error f5 is not a legal move",
+                "ok d6 f4 f6",
+                "ok",
+                "ok c4 d3 e6 f5",
+                "error nothing to undo",
+                "ok",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_passes_and_the_end_of_the_game() {
+        let lines = session(&format!("position XO{} X\nplay c1\n", "-".repeat(62)));
+        assert_eq!(lines, vec!["ok", "ok gameover 3 0"]);
+        // white's only disc left is on a8's side and does not touch a square it could flip from
+        let lines = session(&format!("position -OX{}XO------ X\nplay a1\nlegal\n", "-".repeat(53)));
+        assert_eq!(lines, vec!["ok", "ok pass", "ok c8"]);
+    }
+
+    #[test]
+    fn go_finds_a_legal_move() {
+        let lines = session("go depth 2\ngo movetime 50\ngo sideways\n");
+        for line in &lines[..2] {
+            let words: Vec<&str> = line.split(' ').collect();
+            assert_eq!(words[0], "ok");
+            assert!(["c4", "d3", "e6", "f5"].contains(&words[1]), "{}", line);
+            assert_eq!(words[2], "depth");
+        }
+        assert!(lines[0].starts_with("ok") && lines[0].contains(" depth 2 "));
+        assert!(lines[2].starts_with("error"));
+    }
+
+    #[test]
+    fn show_prints_the_board_before_the_position() {
+        let lines = session("show\n");
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "  a b c d e f g h");
+        assert_eq!(lines[4], "4 - - . X O - - -");
+        assert_eq!(lines[9], "ok ---------------------------XO------OX--------------------------- O");
+    }
+}
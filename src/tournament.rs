@@ -0,0 +1,563 @@
+use crate::minmax::get_for_whoever_best_move;
+use crate::settings::settings;
+use crate::{count_stones, do_move_essentials, init_board_essentials, BoardEssentials, BOARD_SIZE};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+// Headless engine-vs-engine matches, used to measure whether a change to the search or evaluation
+// actually made the AI stronger.
+// Usage: othello tournament --engines 3,weak=2,strong=5 [--gauntlet] [--opening-plies 2] [--rounds 1]
+//                           [--sprt 0,10[,0.05,0.05]]
+// The number of threads comes from the threads setting (--threads).
+
+#[derive(Clone)]
+pub struct EngineConfig {
+    pub name: String,
+    pub depth: usize,
+}
+
+pub struct TournamentSettings {
+    pub engines: Vec<EngineConfig>,
+    pub gauntlet: bool,
+    pub opening_plies: usize,
+    pub rounds: usize,
+    pub threads: usize,
+    pub sprt: Option<Sprt>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+// One scheduled game. `first` plays the side that is to move in the opening.
+struct Pairing {
+    first: usize,
+    second: usize,
+    opening: usize,
+}
+
+struct GameResult {
+    first: usize,
+    second: usize,
+    // from the perspective of `first`: 1.0 win, 0.5 draw, 0.0 loss
+    score: f64,
+    disc_diff: isize,
+}
+
+#[derive(Default, Clone)]
+pub struct MatchStats {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub disc_diff: isize,
+}
+
+impl MatchStats {
+    fn add(&mut self, score: f64, disc_diff: isize) {
+        if score == 1.0 {
+            self.wins += 1;
+        } else if score == 0.0 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+        self.disc_diff += disc_diff;
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    // variance of a single game's score
+    fn variance(&self) -> f64 {
+        let mu = self.score();
+        (self.wins as f64 * (1.0 - mu).powi(2)
+            + self.draws as f64 * (0.5 - mu).powi(2)
+            + self.losses as f64 * mu.powi(2))
+            / self.games() as f64
+    }
+
+    // Elo difference and half-width of its 95% confidence interval, the latter propagated from
+    // the standard error of the score through the slope of the Elo curve. After only wins (or only
+    // losses) the difference is infinite and the interval unbounded.
+    pub fn elo(&self) -> (f64, f64) {
+        let n = self.games() as f64;
+        let mu = self.score();
+        if mu <= 0.0 || mu >= 1.0 {
+            return (elo_from_score(mu), f64::INFINITY);
+        }
+        let standard_error = (self.variance() / n).sqrt();
+        let slope = 400.0 / (10f64.ln() * mu * (1.0 - mu));
+        (elo_from_score(mu), 1.96 * standard_error * slope)
+    }
+
+    // Log-likelihood ratio of H1 (elo1) against H0 (elo0), using the normal approximation of
+    // the game-score distribution.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let mu = self.score();
+        let variance = self.variance();
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (score_from_elo(elo0), score_from_elo(elo1));
+        n * (s1 - s0) * (2.0 * mu - s0 - s1) / (2.0 * variance)
+    }
+}
+
+pub fn elo_from_score(score: f64) -> f64 {
+    if score <= 0.0 {
+        return f64::NEG_INFINITY;
+    } else if score >= 1.0 {
+        return f64::INFINITY;
+    }
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub enum SprtState {
+    Running,
+    AcceptH0,
+    AcceptH1,
+}
+
+impl Sprt {
+    // (lower, upper) LLR bounds at which H0 respectively H1 is accepted
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn state(&self, stats: &MatchStats) -> (SprtState, f64) {
+        let llr = stats.llr(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            (SprtState::AcceptH1, llr)
+        } else if llr <= lower {
+            (SprtState::AcceptH0, llr)
+        } else {
+            (SprtState::Running, llr)
+        }
+    }
+}
+
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let settings = parse_args(args)?;
+    run_tournament(&settings);
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<TournamentSettings, String> {
+    let mut settings = TournamentSettings {
+        engines: vec![],
+        gauntlet: false,
+        opening_plies: 2,
+        rounds: 1,
+        threads: settings().threads,
+        sprt: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or(format!("missing value for {}", name))
+        };
+        match arg.as_str() {
+            "--engines" => settings.engines = parse_engines(&value(arg)?)?,
+            "--gauntlet" => settings.gauntlet = true,
+            "--opening-plies" => settings.opening_plies = parse_number(arg, &value(arg)?)?,
+            "--rounds" => settings.rounds = parse_number(arg, &value(arg)?)?,
+            "--sprt" => settings.sprt = Some(parse_sprt(&value(arg)?)?),
+            _ => return Err(format!("unknown tournament argument '{}'", arg)),
+        }
+    }
+    if settings.engines.len() < 2 {
+        return Err("a tournament needs at least two engines, e.g. --engines 3,5".to_string());
+    }
+    if settings.sprt.is_some() && settings.engines.len() != 2 {
+        return Err("--sprt can only be used for a match between exactly two engines".to_string());
+    }
+    Ok(settings)
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a non-negative number, got '{}'", name, value))
+}
+
+// "3,weak=2,strong=5": a bare number is a depth, "name=depth" gives the engine a name.
+fn parse_engines(value: &str) -> Result<Vec<EngineConfig>, String> {
+    let mut engines = vec![];
+    for spec in value.split(',') {
+        let (name, depth) = match spec.split_once('=') {
+            Some((name, depth)) => (name.to_string(), depth),
+            None => (format!("depth{}", spec), spec),
+        };
+        let depth = parse_number("engine depth", depth)?;
+        if depth == 0 {
+            return Err(format!("engine '{}' needs a depth of at least 1", name));
+        }
+        engines.push(EngineConfig { name, depth });
+    }
+    Ok(engines)
+}
+
+fn parse_sprt(value: &str) -> Result<Sprt, String> {
+    let numbers: Vec<f64> = value
+        .split(',')
+        .map(|v| v.parse().map_err(|_| format!("--sprt expects numbers, got '{}'", v)))
+        .collect::<Result<_, _>>()?;
+    let sprt = match numbers[..] {
+        [elo0, elo1] => Sprt { elo0, elo1, alpha: 0.05, beta: 0.05 },
+        [elo0, elo1, alpha, beta] => Sprt { elo0, elo1, alpha, beta },
+        _ => return Err("--sprt expects elo0,elo1 or elo0,elo1,alpha,beta".to_string()),
+    };
+    let valid_probability = |p: f64| p > 0.0 && p < 1.0;
+    if sprt.elo0 >= sprt.elo1 || !valid_probability(sprt.alpha) || !valid_probability(sprt.beta) {
+        return Err("--sprt needs elo0 < elo1 and alpha, beta between 0 and 1".to_string());
+    }
+    Ok(sprt)
+}
+
+// Every distinct position reachable from the start in exactly `plies` moves. Since each opening
+// is played twice with the engines swapping colours, neither engine profits from an unbalanced one.
+pub fn generate_openings(plies: usize) -> Vec<BoardEssentials> {
+    let mut positions = vec![init_board_essentials()];
+    for _ in 0..plies {
+        let mut next: Vec<BoardEssentials> = vec![];
+        for position in positions.iter() {
+            for x in 0..BOARD_SIZE {
+                for y in 0..BOARD_SIZE {
+                    if position.possible_moves[x][y].is_empty() {
+                        continue;
+                    }
+                    let mut clone = position.clone();
+                    do_move_essentials(x, y, &mut clone);
+                    if clone.game_over {
+                        continue;
+                    }
+                    let seen = next
+                        .iter()
+                        .any(|p| p.white_turn == clone.white_turn && p.board == clone.board);
+                    if !seen {
+                        next.push(clone);
+                    }
+                }
+            }
+        }
+        positions = next;
+    }
+    positions
+}
+
+// Plays a game from `opening` and returns (score, disc differential) from the perspective of
+// the engine that moves first.
+fn play_game(opening: &BoardEssentials, first: &EngineConfig, second: &EngineConfig) -> (f64, isize) {
+    let mut board_essential = opening.clone();
+    let first_is_white = board_essential.white_turn;
+    while !board_essential.game_over {
+        let engine = if board_essential.white_turn == first_is_white { first } else { second };
+        let best = get_for_whoever_best_move(&board_essential, engine.depth).unwrap();
+        do_move_essentials(best.x, best.y, &mut board_essential);
+    }
+    let (black, white) = count_stones(&board_essential.board);
+    let disc_diff = if first_is_white {
+        white as isize - black as isize
+    } else {
+        black as isize - white as isize
+    };
+    let score = match disc_diff {
+        d if d > 0 => 1.0,
+        0 => 0.5,
+        _ => 0.0,
+    };
+    (score, disc_diff)
+}
+
+// Every pair of engines (or the first against each other one in a gauntlet) plays every opening
+// twice per round, once with each engine moving first.
+fn make_schedule(settings: &TournamentSettings, openings: usize) -> Vec<Pairing> {
+    let engines = settings.engines.len();
+    let mut schedule = vec![];
+    for _ in 0..settings.rounds {
+        for opening in 0..openings {
+            for a in 0..engines {
+                for b in (a + 1)..engines {
+                    if settings.gauntlet && a != 0 {
+                        continue;
+                    }
+                    schedule.push(Pairing { first: a, second: b, opening });
+                    schedule.push(Pairing { first: b, second: a, opening });
+                }
+            }
+        }
+    }
+    schedule
+}
+
+pub fn run_tournament(settings: &TournamentSettings) -> Vec<Vec<MatchStats>> {
+    let engines = &settings.engines;
+    let openings = generate_openings(settings.opening_plies);
+    let schedule = make_schedule(settings, openings.len());
+    println!(
+        "Tournament: {} engines, {} openings, {} games on {} threads",
+        engines.len(),
+        openings.len(),
+        schedule.len(),
+        settings.threads
+    );
+
+    // stats[a][b] holds a's results against b
+    let mut stats = vec![vec![MatchStats::default(); engines.len()]; engines.len()];
+    let next_game = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..settings.threads {
+            let sender = sender.clone();
+            let (schedule, openings, next_game, stop) = (&schedule, &openings, &next_game, &stop);
+            scope.spawn(move || loop {
+                let index = next_game.fetch_add(1, Ordering::SeqCst);
+                if index >= schedule.len() || stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let pairing = &schedule[index];
+                let (score, disc_diff) =
+                    play_game(&openings[pairing.opening], &engines[pairing.first], &engines[pairing.second]);
+                let result = GameResult { first: pairing.first, second: pairing.second, score, disc_diff };
+                if sender.send(result).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for (played, result) in receiver.iter().enumerate() {
+            stats[result.first][result.second].add(result.score, result.disc_diff);
+            stats[result.second][result.first].add(1.0 - result.score, -result.disc_diff);
+            println!(
+                "Game {}/{}: {} vs {}: {}",
+                played + 1,
+                schedule.len(),
+                engines[result.first].name,
+                engines[result.second].name,
+                if result.score == 1.0 {
+                    "1-0"
+                } else if result.score == 0.0 {
+                    "0-1"
+                } else {
+                    "1/2-1/2"
+                }
+            );
+            if let Some(sprt) = settings.sprt {
+                let (state, llr) = sprt.state(&stats[0][1]);
+                // the games still being played when the test stops are counted but not announced again
+                match state {
+                    SprtState::Running => (),
+                    SprtState::AcceptH0 | SprtState::AcceptH1 if !stop.load(Ordering::SeqCst) => {
+                        println!("SPRT finished with LLR {:.2}", llr);
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                    SprtState::AcceptH0 | SprtState::AcceptH1 => (),
+                }
+            }
+        }
+    });
+
+    print_report(settings, &stats);
+    stats
+}
+
+fn print_report(settings: &TournamentSettings, stats: &[Vec<MatchStats>]) {
+    let engines = &settings.engines;
+    println!("\nResults:");
+    for a in 0..engines.len() {
+        for b in (a + 1)..engines.len() {
+            let match_stats = &stats[a][b];
+            if match_stats.games() == 0 {
+                continue;
+            }
+            let (elo, margin) = match_stats.elo();
+            let elo = if elo.is_finite() { format!("{:+.1} +/- {:.1}", elo, margin) } else { "unbounded".to_string() };
+            println!(
+                "  {} vs {}: +{} ={} -{} ({} games), score {:.1}%, discs {:+.2}/game, Elo {}",
+                engines[a].name,
+                engines[b].name,
+                match_stats.wins,
+                match_stats.draws,
+                match_stats.losses,
+                match_stats.games(),
+                100.0 * match_stats.score(),
+                match_stats.disc_diff as f64 / match_stats.games() as f64,
+                elo
+            );
+        }
+    }
+
+    println!("\nStandings:");
+    for (a, engine) in engines.iter().enumerate() {
+        let total = stats[a].iter().fold(MatchStats::default(), |mut total, s| {
+            total.wins += s.wins;
+            total.draws += s.draws;
+            total.losses += s.losses;
+            total.disc_diff += s.disc_diff;
+            total
+        });
+        if total.games() == 0 {
+            continue;
+        }
+        println!(
+            "  {:<12} depth {:<2} +{} ={} -{}  score {:.1}%",
+            engine.name,
+            engine.depth,
+            total.wins,
+            total.draws,
+            total.losses,
+            100.0 * total.score()
+        );
+    }
+
+    if let Some(sprt) = settings.sprt {
+        let (state, llr) = sprt.state(&stats[0][1]);
+        let (lower, upper) = sprt.bounds();
+        println!(
+            "\nSPRT [{}, {}] LLR {:.2} ({:.2}, {:.2}): {}",
+            sprt.elo0,
+            sprt.elo1,
+            llr,
+            lower,
+            upper,
+            match state {
+                SprtState::Running => "inconclusive",
+                SprtState::AcceptH0 => "H0 accepted",
+                SprtState::AcceptH1 => "H1 accepted",
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(wins: usize, draws: usize, losses: usize) -> MatchStats {
+        MatchStats { wins, draws, losses, disc_diff: 0 }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn reads_engines_gauntlet_and_sprt() {
+        let settings = parse_args(&args("--engines 3,weak=2 --gauntlet --opening-plies 4 --rounds 2 --sprt 0,50")).unwrap();
+        let engines: Vec<(&str, usize)> = settings.engines.iter().map(|e| (e.name.as_str(), e.depth)).collect();
+        assert_eq!(engines, [("depth3", 3), ("weak", 2)]);
+        assert!(settings.gauntlet);
+        assert_eq!((settings.opening_plies, settings.rounds), (4, 2));
+        let sprt = settings.sprt.unwrap();
+        assert_eq!((sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta), (0.0, 50.0, 0.05, 0.05));
+        let sprt = parse_args(&args("--engines 1,2 --sprt -5,5,0.1,0.2")).unwrap().sprt.unwrap();
+        assert_eq!((sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta), (-5.0, 5.0, 0.1, 0.2));
+
+        let settings = parse_args(&args("--engines 1,2,3")).unwrap();
+        assert!(!settings.gauntlet && settings.sprt.is_none());
+        assert_eq!((settings.opening_plies, settings.rounds), (2, 1));
+
+        let error = |text: &str| parse_args(&args(text)).err().unwrap();
+        assert!(error("--engines 3").contains("at least two engines"));
+        assert!(error("--engines 3,0").contains("depth of at least 1"));
+        assert!(error("--engines 3,x").contains("non-negative number"));
+        assert!(error("--engines 1,2,3 --sprt 0,10").contains("exactly two engines"));
+        assert!(error("--engines 1,2 --sprt 10,0").contains("elo0 < elo1"));
+        assert!(error("--engines 1,2 --sprt 0").contains("elo0,elo1"));
+        assert!(error("--engines 1,2 --fast").contains("unknown tournament argument"));
+        assert!(error("--engines").contains("missing value"));
+    }
+
+    #[test]
+    fn every_pair_plays_each_opening_with_both_colours() {
+        let settings = parse_args(&args("--engines 1,a=1,b=1 --opening-plies 1")).unwrap();
+        let openings = generate_openings(1).len();
+        assert_eq!(openings, 4);
+        let schedule = make_schedule(&settings, openings);
+        assert_eq!(schedule.len(), 3 * 2 * openings);
+        for first in 0..3 {
+            for second in (0..3).filter(|&second| second != first) {
+                for opening in 0..openings {
+                    let games = schedule
+                        .iter()
+                        .filter(|game| (game.first, game.second, game.opening) == (first, second, opening))
+                        .count();
+                    assert_eq!(games, 1);
+                }
+            }
+        }
+
+        let stats = run_tournament(&settings);
+        for (a, row) in stats.iter().enumerate() {
+            assert_eq!(row[a].games(), 0);
+            for b in (0..3).filter(|&b| b != a) {
+                assert_eq!(row[b].games(), 2 * openings);
+                assert_eq!((row[b].wins, row[b].draws), (stats[b][a].losses, stats[b][a].draws));
+                assert_eq!(row[b].disc_diff, -stats[b][a].disc_diff);
+                // equal engines: each wins with one colour what it loses with the other
+                assert_eq!(row[b].wins, row[b].losses);
+            }
+        }
+
+        let gauntlet = parse_args(&args("--engines 1,2,3 --gauntlet --rounds 2")).unwrap();
+        let schedule = make_schedule(&gauntlet, openings);
+        assert_eq!(schedule.len(), 2 * 2 * 2 * openings);
+        assert!(schedule.iter().all(|game| game.first == 0 || game.second == 0));
+    }
+
+    #[test]
+    fn elo_and_its_margin() {
+        assert_eq!(elo_from_score(0.5), 0.0);
+        assert!(close(elo_from_score(0.75), 190.848501888));
+        assert!(close(elo_from_score(0.25), -190.848501888));
+        let (elo, margin) = stats(3, 0, 1).elo();
+        assert!(close(elo, 190.848501888));
+        assert!(close(margin, 393.160376502));
+        // a clean sweep has no finite margin
+        assert_eq!(stats(4, 0, 0).elo(), (f64::INFINITY, f64::INFINITY));
+        assert_eq!(stats(0, 0, 4).elo(), (f64::NEG_INFINITY, f64::INFINITY));
+    }
+
+    #[test]
+    fn llr_and_sprt_bounds() {
+        assert!(close(stats(60, 20, 20).llr(0.0, 10.0), 1.733713312));
+        assert!(close(stats(30, 40, 30).llr(0.0, 10.0), -0.068997023));
+        assert_eq!(stats(0, 0, 0).llr(0.0, 10.0), 0.0);
+        assert_eq!(stats(0, 5, 0).llr(0.0, 10.0), 0.0);
+
+        let sprt = Sprt { elo0: 0.0, elo1: 10.0, alpha: 0.05, beta: 0.05 };
+        let (lower, upper) = sprt.bounds();
+        assert!(close(lower, -2.944438979) && close(upper, 2.944438979));
+        assert!(matches!(sprt.state(&stats(90, 0, 10)), (SprtState::AcceptH1, _)));
+        assert!(matches!(sprt.state(&stats(10, 0, 90)), (SprtState::AcceptH0, _)));
+        assert!(matches!(sprt.state(&stats(60, 20, 20)), (SprtState::Running, _)));
+    }
+}
@@ -0,0 +1,325 @@
+use crate::analysis::{AiMove, Evaluator};
+use crate::controller::{Controller, Player};
+use crate::savegame::{autosaved_game, Autosave};
+use crate::terminal::{ask_to_resume, colour_name, controller_from_args, final_score};
+use crate::{count_stones, Colour, Square, BOARD_SIZE};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::time::Duration;
+
+// A full-screen terminal front-end: the board on the left, moved over with the arrow keys, and a
+// panel on the right with the disc counts, clocks, the engine's evaluation and the moves played.
+// Keys: arrows move the cursor, enter or space plays, u undoes, r redoes, home and end go to the
+// start and end of the moves played, h shows a hint, q quits.
+
+struct App {
+    controller: Controller,
+    cursor: Square,
+    hint: Option<Square>,
+    // the evaluations of the positions, searched in the background
+    evaluator: Evaluator,
+    // the AI's search for its move, while it is thinking
+    ai_search: Option<AiMove>,
+    message: String,
+    quit: bool,
+}
+
+impl App {
+    fn new(controller: Controller) -> App {
+        let centre = BOARD_SIZE / 2 - 1;
+        App {
+            evaluator: Evaluator::new(controller.depth),
+            controller,
+            cursor: Square { x: centre, y: centre },
+            hint: None,
+            ai_search: None,
+            message: String::new(),
+            quit: false,
+        }
+    }
+
+    fn position_changed(&mut self, message: String) {
+        self.hint = None;
+        self.message = message;
+    }
+
+    fn handle_key(&mut self, key: KeyCode) {
+        let cursor = &mut self.cursor;
+        match key {
+            KeyCode::Left => cursor.x = (cursor.x + BOARD_SIZE - 1) % BOARD_SIZE,
+            KeyCode::Right => cursor.x = (cursor.x + 1) % BOARD_SIZE,
+            KeyCode::Up => cursor.y = (cursor.y + BOARD_SIZE - 1) % BOARD_SIZE,
+            KeyCode::Down => cursor.y = (cursor.y + 1) % BOARD_SIZE,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if !self.controller.human_to_move() {
+                    return;
+                }
+                let square = self.cursor.clone();
+                match self.controller.play(square.clone()) {
+                    Ok(()) => self.position_changed(format!("Played {}", square)),
+                    Err(error) => self.message = error,
+                }
+            }
+            KeyCode::Char('u') => {
+                if self.controller.undo_to_human() {
+                    self.position_changed("Move taken back".to_string());
+                } else {
+                    self.message = "Nothing to undo".to_string();
+                }
+            }
+            KeyCode::Char('r') => {
+                if self.controller.redo_to_human() {
+                    self.position_changed("Move replayed".to_string());
+                } else {
+                    self.message = "Nothing to redo".to_string();
+                }
+            }
+            KeyCode::Home => {
+                self.controller.go_to(0);
+                self.position_changed("Start of the game".to_string());
+            }
+            KeyCode::End => {
+                self.controller.go_to(self.controller.history_len());
+                self.position_changed("Last move".to_string());
+            }
+            // a hint shown already is not asked for (and counted) again
+            KeyCode::Char('h') if self.controller.human_to_move() && self.hint.is_none() => {
+                self.hint = self.controller.take_hint(1).into_iter().next().map(|(square, _)| square);
+                if let Some(hint) = &self.hint {
+                    self.message = format!("Hint: {}", hint);
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => (),
+        }
+    }
+
+    // Starts the AI's search for its move in this position, or plays the move once it is found.
+    fn ai_move(&mut self) {
+        let board_essential = &self.controller.game.board_essential;
+        let found = match &self.ai_search {
+            Some(search) if search.is_for(board_essential) => search.poll(),
+            _ => {
+                self.ai_search = self.controller.ai_search().map(|search| AiMove::start(board_essential, search));
+                None
+            }
+        };
+        if let Some(square) = found {
+            // the same position may come back after an undo
+            self.ai_search = None;
+            let white = board_essential.white_turn;
+            self.controller.play(square.clone()).expect("the AI plays legal moves");
+            self.position_changed(format!("{} (AI) played {}", colour_name(white), square));
+        }
+    }
+
+    fn status(&self) -> String {
+        let game = &self.controller.game;
+        if game.board_essential.game_over {
+            return final_score(game).lines().next().unwrap_or("").to_string();
+        }
+        let white = game.board_essential.white_turn;
+        match self.controller.player_to_move() {
+            Player::Human => format!("{} to move", colour_name(white)),
+            Player::Ai => format!("{} (AI) is thinking...", colour_name(white)),
+        }
+    }
+}
+
+fn board_lines(app: &App) -> Vec<Line<'static>> {
+    let game = &app.controller.game;
+    let show_moves = app.controller.human_to_move();
+    let mut lines = vec![Line::from(
+        (0..BOARD_SIZE).fold("  ".to_string(), |text, x| text + &format!(" {} ", (b'a' + x as u8) as char)),
+    )];
+    for y in 0..BOARD_SIZE {
+        let mut spans = vec![Span::raw(format!("{} ", y + 1))];
+        for x in 0..BOARD_SIZE {
+            let square = Square { x, y };
+            let (text, mut style) = match game.board_essential.board[x][y] {
+                Colour::BLACK => (" X ", Style::default().fg(Color::Black).bg(Color::Green)),
+                Colour::WHITE => (" O ", Style::default().fg(Color::White).bg(Color::Green)),
+                Colour::EMPTY if show_moves && !game.board_essential.possible_moves[x][y].is_empty() => {
+                    (" . ", Style::default().fg(Color::Yellow).bg(Color::Green))
+                }
+                Colour::EMPTY => ("   ", Style::default().bg(Color::Green)),
+            };
+            if game.moves.last() == Some(&square) {
+                style = style.add_modifier(Modifier::BOLD).bg(Color::LightGreen);
+            }
+            if app.hint.as_ref() == Some(&square) {
+                style = style.bg(Color::Magenta);
+            }
+            if app.cursor == square {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(text, style));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn panel_lines(app: &App) -> Vec<Line<'static>> {
+    let game = &app.controller.game;
+    let (black, white) = count_stones(&game.board_essential.board);
+    let player = |white: bool| match app.controller.players[white as usize] {
+        Player::Human => "human",
+        Player::Ai => "AI",
+    };
+    let evaluation = match app.evaluator.value(&game.board_essential) {
+        Some(isize::MAX) => "white wins".to_string(),
+        Some(isize::MIN) => "black wins".to_string(),
+        Some(value) => format!("{:+} (white's view)", value),
+        None => "-".to_string(),
+    };
+    // the time left under a time control, otherwise the time used
+    let time = |white: bool| clock(app.controller.remaining(white).unwrap_or_else(|| app.controller.clock(white)));
+    let mut lines = vec![
+        Line::from(format!("Black (X, {}): {:>2}  {}", player(false), black, time(false))),
+        Line::from(format!("White (O, {}): {:>2}  {}", player(true), white, time(true))),
+        Line::from(format!("Evaluation: {}", evaluation)),
+        Line::from(""),
+        Line::from("Moves:"),
+    ];
+    // passes are not stored, so the moves are numbered one by one rather than in pairs
+    let moves: Vec<String> = game.moves.iter().enumerate().map(|(i, sq)| format!("{:>2}. {}", i + 1, sq)).collect();
+    for row in moves.chunks(4) {
+        lines.push(Line::from(row.join("  ")));
+    }
+    lines
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [main, status] = Layout::vertical([Constraint::Min(BOARD_SIZE as u16 + 3), Constraint::Length(3)]).areas(frame.area());
+    let [board, panel] =
+        Layout::horizontal([Constraint::Length(3 * BOARD_SIZE as u16 + 4), Constraint::Min(30)]).areas(main);
+    frame.render_widget(Paragraph::new(board_lines(app)).block(Block::bordered().title(" Othello ")), board);
+    frame.render_widget(
+        Paragraph::new(panel_lines(app)).wrap(Wrap { trim: false }).block(Block::bordered().title(" Game ")),
+        panel,
+    );
+    let text = format!("{}  {}", app.status(), app.message);
+    let help = " arrows move, enter plays, u undo, r redo, home/end, h hint, q quit ";
+    frame.render_widget(Paragraph::new(text).block(Block::bordered().title_bottom(help)), status);
+}
+
+fn run(terminal: &mut DefaultTerminal, app: &mut App) -> std::io::Result<()> {
+    let mut autosave = Autosave::from_settings(&app.controller);
+    while !app.quit {
+        autosave.update(&app.controller);
+        app.evaluator.request(&app.controller.game.board_essential);
+        app.evaluator.poll();
+        // the AI waits while moves taken back are being gone through
+        if app.controller.ai_to_move() && !app.controller.can_redo() {
+            app.ai_move();
+        }
+        terminal.draw(|frame| draw(frame, app))?;
+        // wake up every so often to keep the clock running and to take in what the searches found
+        if event::poll(Duration::from_millis(50))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key.code);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Usage: othello tui [--black human|ai] [--white human|ai] [--moves|--position|--ggf|--load ...|--resume]
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let args = ask_to_resume(args, autosaved_game().is_some(), std::io::stdin().lock(), &mut std::io::stdout())?;
+    let mut app = App::new(controller_from_args(&args)?);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_game;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::time::Instant;
+
+    fn humans() -> App {
+        App::new(Controller::new(init_game(), [Player::Human, Player::Human], 1))
+    }
+
+    #[test]
+    fn keys_move_the_cursor_and_play() {
+        let mut app = humans();
+        app.handle_key(KeyCode::Left);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.controller.game.moves, vec!["c4".parse().unwrap()]);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.message, "c4 is not a legal move");
+        app.handle_key(KeyCode::Char('u'));
+        assert!(app.controller.game.moves.is_empty());
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(app.controller.game.moves.len(), 1);
+        app.handle_key(KeyCode::Up);
+        app.handle_key(KeyCode::Up);
+        app.handle_key(KeyCode::Up);
+        app.handle_key(KeyCode::Up);
+        assert_eq!(app.cursor, Square { x: 2, y: 7 });
+    }
+
+    #[test]
+    fn hint_marks_a_legal_move() {
+        let mut app = humans();
+        app.handle_key(KeyCode::Char('h'));
+        let hint = app.hint.clone().unwrap();
+        assert!(crate::legal_moves(&app.controller.game.board_essential).contains(&hint));
+        assert_eq!(app.message, format!("Hint: {}", hint));
+        app.handle_key(KeyCode::Char('h'));
+        assert_eq!(app.controller.game.info.hints, [0, 1]);
+    }
+
+    #[test]
+    fn the_ai_thinks_in_the_background_and_plays_its_moves() {
+        let mut app = App::new(Controller::new(init_game(), [Player::Ai, Player::Ai], 1));
+        let started = Instant::now();
+        while app.controller.game.moves.len() < 2 {
+            assert!(started.elapsed() < Duration::from_secs(30), "no AI moves");
+            assert!(app.status().ends_with("(AI) is thinking..."));
+            app.ai_move();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let white = app.controller.game.board_essential.white_turn;
+        let square = app.controller.game.moves[1].clone();
+        assert_eq!(app.message, format!("{} (AI) played {}", colour_name(!white), square));
+        assert!(app.ai_search.is_none());
+        // going back starts a search in the position gone back to
+        app.controller.go_to(0);
+        app.ai_move();
+        assert!(app.ai_search.as_ref().is_some_and(|search| search.is_for(&app.controller.game.board_essential)));
+    }
+
+    #[test]
+    fn draws_board_and_panel() {
+        let mut app = humans();
+        app.handle_key(KeyCode::Left);
+        app.handle_key(KeyCode::Enter);
+        let mut terminal = Terminal::new(TestBackend::new(70, 16)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Black (X, human):  1"), "{}", text);
+        assert!(text.contains("White (O, human):  4"));
+        assert!(text.contains(" 1. c4"));
+        assert!(text.contains("Black to move  Played c4"));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{do_move_essentials, legal_moves, BoardEssentials, Square};
+use std::fmt;
+
+// The moves tried from a position, as a tree: going back and playing another move starts a
+// variation instead of throwing the old moves away. Of the moves after a position the first is
+// the main line and the others are variations.
+// Written down like a transcript with the variations in brackets after the move they replace,
+// e.g. "f5d6c3(c5f6)d3c4": after f5d6 the main line goes on with c3d3c4, the variation with c5f6.
+// A position in the tree is a path: the index of the move taken at each ply.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub square: Square,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tree {
+    // the moves from the start position
+    pub roots: Vec<Node>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VariationError {
+    Syntax(String),
+    IllegalMove { move_number: usize, square: Square },
+}
+
+impl fmt::Display for VariationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VariationError::Syntax(message) => write!(f, "variations: {}", message),
+            VariationError::IllegalMove { move_number, square } => {
+                write!(f, "variations: move {}: {} is not a legal move", move_number, square)
+            }
+        }
+    }
+}
+
+// The ply of the last move along `path` that leaves the main line, which is where the variation
+// the path is in branched off.
+pub fn branch_point(path: &[usize]) -> Option<usize> {
+    path.iter().rposition(|&index| index != 0)
+}
+
+impl Tree {
+    // The moves played so far after the position at `path`.
+    pub fn children(&self, path: &[usize]) -> &[Node] {
+        path.iter().fold(&self.roots, |children, &index| &children[index].children)
+    }
+
+    fn children_mut(&mut self, path: &[usize]) -> &mut Vec<Node> {
+        path.iter().fold(&mut self.roots, |children, &index| &mut children[index].children)
+    }
+
+    // Adds `square` after the position at `path` unless it was played there before, and returns
+    // its index.
+    pub fn add(&mut self, path: &[usize], square: Square) -> usize {
+        let children = self.children_mut(path);
+        match children.iter().position(|node| node.square == square) {
+            Some(index) => index,
+            None => {
+                children.push(Node { square, children: vec![] });
+                children.len() - 1
+            }
+        }
+    }
+
+    // The moves that follow `path`, taking `line` where it goes on and the main line after that.
+    pub fn continuation(&self, path: &[usize], line: &[usize]) -> Vec<usize> {
+        let mut full = path.to_vec();
+        loop {
+            let children = self.children(&full);
+            if children.is_empty() {
+                return full[path.len()..].to_vec();
+            }
+            let index = line.get(full.len()).copied().filter(|&index| index < children.len()).unwrap_or(0);
+            full.push(index);
+        }
+    }
+
+    // Whether any position has more than one move after it.
+    pub fn has_variations(&self) -> bool {
+        fn branches(children: &[Node]) -> bool {
+            children.len() > 1 || children.iter().any(|node| branches(&node.children))
+        }
+        branches(&self.roots)
+    }
+
+    // Makes the variation `path` is in the main line at its branch point. Returns that ply; the
+    // path is then the same apart from a 0 there.
+    pub fn promote(&mut self, path: &[usize]) -> Option<usize> {
+        let ply = branch_point(path)?;
+        let children = self.children_mut(&path[..ply]);
+        let node = children.remove(path[ply]);
+        children.insert(0, node);
+        Some(ply)
+    }
+
+    // Removes the move at the end of `path` and everything after it.
+    pub fn remove(&mut self, path: &[usize]) {
+        if let Some((&last, parent)) = path.split_last() {
+            self.children_mut(parent).remove(last);
+        }
+    }
+}
+
+fn write_children(children: &[Node], text: &mut String) {
+    let Some((main, variations)) = children.split_first() else {
+        return;
+    };
+    text.push_str(&main.square.to_string());
+    for variation in variations {
+        text.push('(');
+        text.push_str(&variation.square.to_string());
+        write_children(&variation.children, text);
+        text.push(')');
+    }
+    write_children(&main.children, text);
+}
+
+pub fn write_tree(tree: &Tree) -> String {
+    let mut text = String::new();
+    write_children(&tree.roots, &mut text);
+    text
+}
+
+fn position_at(start: &BoardEssentials, tree: &Tree, path: &[usize]) -> BoardEssentials {
+    let mut position = start.clone();
+    for ply in 0..path.len() {
+        let square = &tree.children(&path[..ply])[path[ply]].square;
+        do_move_essentials(square.x, square.y, &mut position);
+    }
+    position
+}
+
+// Reads a tree written by write_tree, checking the moves from `start`. Whitespace is ignored.
+pub fn parse_tree(text: &str, start: &BoardEssentials) -> Result<Tree, VariationError> {
+    let characters: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut tree = Tree::default();
+    let mut path: Vec<usize> = vec![];
+    // where to go back to at the end of each open variation
+    let mut open: Vec<Vec<usize>> = vec![];
+    let mut i = 0;
+    while i < characters.len() {
+        match characters[i] {
+            '(' => {
+                if path.is_empty() {
+                    return Err(VariationError::Syntax("a variation needs a move before it".to_string()));
+                }
+                open.push(path.clone());
+                path.pop();
+                i += 1;
+            }
+            ')' => {
+                path = open.pop().ok_or(VariationError::Syntax("unmatched ')'".to_string()))?;
+                i += 1;
+            }
+            _ => {
+                let text: String = characters[i..(i + 2).min(characters.len())].iter().collect();
+                let square: Square =
+                    text.parse().map_err(|_| VariationError::Syntax(format!("'{}' is not a square", text)))?;
+                let position = position_at(start, &tree, &path);
+                if position.game_over || !legal_moves(&position).contains(&square) {
+                    return Err(VariationError::IllegalMove { move_number: path.len() + 1, square });
+                }
+                let index = tree.add(&path, square);
+                path.push(index);
+                i += 2;
+            }
+        }
+    }
+    if !open.is_empty() {
+        return Err(VariationError::Syntax("missing ')'".to_string()));
+    }
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_board_essentials;
+
+    fn square(text: &str) -> Square {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn variations_are_added_promoted_and_removed() {
+        let mut tree = Tree::default();
+        let c4 = tree.add(&[], square("c4"));
+        let c3 = tree.add(&[c4], square("c3"));
+        let e3 = tree.add(&[c4], square("e3"));
+        assert_eq!((c3, e3), (0, 1));
+        assert_eq!(tree.add(&[c4], square("e3")), 1);
+        tree.add(&[c4, e3], square("f4"));
+        assert!(tree.has_variations());
+        assert_eq!(write_tree(&tree), "c4c3(e3f4)");
+        assert_eq!(tree.continuation(&[0], &[]), vec![0]);
+        assert_eq!(tree.continuation(&[0], &[0, 1]), vec![1, 0]);
+
+        assert_eq!(branch_point(&[0, 1, 0]), Some(1));
+        assert_eq!(tree.promote(&[0, 1, 0]), Some(1));
+        assert_eq!(write_tree(&tree), "c4e3(c3)f4");
+        assert_eq!(tree.promote(&[0, 0, 0]), None);
+        tree.remove(&[0, 1]);
+        assert!(!tree.has_variations());
+        assert_eq!(write_tree(&tree), "c4e3f4");
+    }
+
+    #[test]
+    fn trees_round_trip() {
+        let start = init_board_essentials();
+        for text in ["", "c4", "c4c3(e3f4(f6))c2(e6)b4", "c4(d3)(e6f4)c3"] {
+            let tree = parse_tree(text, &start).unwrap();
+            assert_eq!(write_tree(&tree), text);
+        }
+        assert_eq!(parse_tree("c4 c3 (e3 f4)", &start).unwrap(), parse_tree("c4c3(e3f4)", &start).unwrap());
+    }
+
+    #[test]
+    fn bad_trees_are_rejected() {
+        let start = init_board_essentials();
+        assert_eq!(
+            parse_tree("c4(a1)", &start),
+            Err(VariationError::IllegalMove { move_number: 1, square: square("a1") })
+        );
+        assert!(matches!(parse_tree("(c4)", &start), Err(VariationError::Syntax(_))));
+        assert!(matches!(parse_tree("c4(d3", &start), Err(VariationError::Syntax(_))));
+        assert!(matches!(parse_tree("c4)", &start), Err(VariationError::Syntax(_))));
+        assert!(matches!(parse_tree("c4x9", &start), Err(VariationError::Syntax(_))));
+    }
+}
@@ -0,0 +1,437 @@
+use crate::{board_essentials_from_board, do_move, game_from_board_essentials, legal_moves, Colour, Game, Square, BOARD_SIZE};
+use std::fmt;
+
+// Reader for the WTHOR database of the French Othello Federation: .wtb files with the games of one
+// year, WTHOR.JOU with the player names and WTHOR.TRN with the tournament names.
+// Every file starts with a 16 byte header. The .wtb records are 68 bytes: tournament, black player
+// and white player numbers (u16, little endian), black's disc count, black's theoretical disc count,
+// then 60 moves as 10 * row + column (both 1-8, so 56 is f5), 0 after the last move.
+// Games start from the standard position with black to move and passes are not recorded.
+// Usage: othello wthor [--jou WTHOR.JOU] [--trn WTHOR.TRN] [--player name] [--black name]
+//                      [--white name] [--tournament name] [--year year] <file.wtb>...
+
+const HEADER_SIZE: usize = 16;
+const GAME_SIZE: usize = 68;
+const PLAYER_SIZE: usize = 20;
+const TOURNAMENT_SIZE: usize = 26;
+
+#[derive(Debug, PartialEq)]
+pub enum WthorError {
+    Truncated { expected: usize, found: usize },
+    UnsupportedBoard(u8),
+    InvalidMove { game: usize, move_number: usize, value: u8 },
+    IllegalMove { game: usize, move_number: usize, square: Square },
+    Io(String),
+}
+
+impl fmt::Display for WthorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WthorError::Truncated { expected, found } => {
+                write!(f, "file is truncated, expected {} bytes but found {}", expected, found)
+            }
+            WthorError::UnsupportedBoard(size) => write!(f, "unsupported board size {}", size),
+            WthorError::InvalidMove { game, move_number, value } => {
+                write!(f, "game {} move {}: {} is not a square", game, move_number, value)
+            }
+            WthorError::IllegalMove { game, move_number, square } => {
+                write!(f, "game {} move {}: {} is not legal", game, move_number, square)
+            }
+            WthorError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+pub struct WthorHeader {
+    pub records: usize,
+    pub year: u16,
+    pub board_size: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WthorGame {
+    // position of the game in its file, counted from 0
+    pub index: usize,
+    pub year: u16,
+    pub tournament: u16,
+    pub black: u16,
+    pub white: u16,
+    pub black_score: u8,
+    pub theoretical_score: u8,
+    pub moves: Vec<Square>,
+}
+
+pub fn parse_header(bytes: &[u8]) -> Result<WthorHeader, WthorError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(WthorError::Truncated { expected: HEADER_SIZE, found: bytes.len() });
+    }
+    let games = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let names = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    Ok(WthorHeader {
+        // .wtb files count their games in the first field, .jou and .trn files in the second
+        records: if games > 0 { games } else { names },
+        year: u16::from_le_bytes([bytes[10], bytes[11]]),
+        board_size: bytes[12],
+    })
+}
+
+fn check_length(bytes: &[u8], records: usize, record_size: usize) -> Result<(), WthorError> {
+    let expected = HEADER_SIZE + records * record_size;
+    if bytes.len() < expected {
+        return Err(WthorError::Truncated { expected, found: bytes.len() });
+    }
+    Ok(())
+}
+
+pub fn parse_games(bytes: &[u8]) -> Result<Vec<WthorGame>, WthorError> {
+    let header = parse_header(bytes)?;
+    if header.board_size != 0 && header.board_size as usize != BOARD_SIZE {
+        return Err(WthorError::UnsupportedBoard(header.board_size));
+    }
+    check_length(bytes, header.records, GAME_SIZE)?;
+
+    let mut games = Vec::with_capacity(header.records);
+    for index in 0..header.records {
+        let record = &bytes[HEADER_SIZE + index * GAME_SIZE..HEADER_SIZE + (index + 1) * GAME_SIZE];
+        let mut moves = vec![];
+        for (move_index, value) in record[8..].iter().enumerate() {
+            if *value == 0 {
+                break;
+            }
+            let (row, column) = ((value / 10) as usize, (value % 10) as usize);
+            if !(1..=BOARD_SIZE).contains(&row) || !(1..=BOARD_SIZE).contains(&column) {
+                return Err(WthorError::InvalidMove { game: index, move_number: move_index + 1, value: *value });
+            }
+            moves.push(Square { x: column - 1, y: row - 1 });
+        }
+        games.push(WthorGame {
+            index,
+            year: header.year,
+            tournament: u16::from_le_bytes([record[0], record[1]]),
+            black: u16::from_le_bytes([record[2], record[3]]),
+            white: u16::from_le_bytes([record[4], record[5]]),
+            black_score: record[6],
+            theoretical_score: record[7],
+            moves,
+        });
+    }
+    Ok(games)
+}
+
+// Player (.jou) and tournament (.trn) files are fixed size, zero padded Latin-1 names.
+fn parse_names(bytes: &[u8], record_size: usize) -> Result<Vec<String>, WthorError> {
+    let header = parse_header(bytes)?;
+    check_length(bytes, header.records, record_size)?;
+    Ok((0..header.records)
+        .map(|index| {
+            let record = &bytes[HEADER_SIZE + index * record_size..HEADER_SIZE + (index + 1) * record_size];
+            record
+                .iter()
+                .take_while(|b| **b != 0)
+                .map(|b| *b as char)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect())
+}
+
+pub fn parse_players(bytes: &[u8]) -> Result<Vec<String>, WthorError> {
+    parse_names(bytes, PLAYER_SIZE)
+}
+
+pub fn parse_tournaments(bytes: &[u8]) -> Result<Vec<String>, WthorError> {
+    parse_names(bytes, TOURNAMENT_SIZE)
+}
+
+fn standard_start() -> Game {
+    let mut board = [[Colour::EMPTY; BOARD_SIZE]; BOARD_SIZE];
+    board[3][3] = Colour::WHITE;
+    board[4][4] = Colour::WHITE;
+    board[3][4] = Colour::BLACK;
+    board[4][3] = Colour::BLACK;
+    game_from_board_essentials(board_essentials_from_board(board, false))
+}
+
+impl WthorGame {
+    // Plays the moves with this program's rules, failing at the first illegal one.
+    pub fn replay(&self) -> Result<Game, WthorError> {
+        let mut game = standard_start();
+        for (move_index, square) in self.moves.iter().enumerate() {
+            if game.board_essential.game_over || !legal_moves(&game.board_essential).contains(square) {
+                return Err(WthorError::IllegalMove {
+                    game: self.index,
+                    move_number: move_index + 1,
+                    square: square.clone(),
+                });
+            }
+            do_move(square.x, square.y, &mut game);
+        }
+        game.info.date = Some(self.year.to_string());
+        game.info.result = Some(format!("{:+}.000", 2 * self.black_score as isize - 64));
+        Ok(game)
+    }
+}
+
+#[derive(Default)]
+pub struct WthorDatabase {
+    pub players: Vec<String>,
+    pub tournaments: Vec<String>,
+    pub games: Vec<WthorGame>,
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, WthorError> {
+    std::fs::read(path).map_err(|e| WthorError::Io(format!("cannot read {}: {}", path, e)))
+}
+
+impl WthorDatabase {
+    pub fn load(wtb_paths: &[String], jou_path: Option<&str>, trn_path: Option<&str>) -> Result<Self, WthorError> {
+        let mut database = WthorDatabase::default();
+        if let Some(path) = jou_path {
+            database.players = parse_players(&read_file(path)?)?;
+        }
+        if let Some(path) = trn_path {
+            database.tournaments = parse_tournaments(&read_file(path)?)?;
+        }
+        for path in wtb_paths {
+            database.games.extend(parse_games(&read_file(path)?)?);
+        }
+        Ok(database)
+    }
+
+    pub fn player_name(&self, id: u16) -> String {
+        match self.players.get(id as usize) {
+            Some(name) => name.clone(),
+            None => format!("player #{}", id),
+        }
+    }
+
+    pub fn tournament_name(&self, id: u16) -> String {
+        match self.tournaments.get(id as usize) {
+            Some(name) => name.clone(),
+            None => format!("tournament #{}", id),
+        }
+    }
+
+    pub fn query(&self) -> WthorQuery<'_> {
+        WthorQuery { database: self, player: None, black: None, white: None, tournament: None, year: None }
+    }
+
+    // Replays a game and fills in the players and tournament.
+    pub fn replay(&self, wthor_game: &WthorGame) -> Result<Game, WthorError> {
+        let mut game = wthor_game.replay()?;
+        game.info.black_player = Some(self.player_name(wthor_game.black));
+        game.info.white_player = Some(self.player_name(wthor_game.white));
+        game.info.place = Some(self.tournament_name(wthor_game.tournament));
+        Ok(game)
+    }
+}
+
+// Filters over the games of a database. Names match case-insensitively on any part of the name.
+pub struct WthorQuery<'a> {
+    database: &'a WthorDatabase,
+    player: Option<String>,
+    black: Option<String>,
+    white: Option<String>,
+    tournament: Option<String>,
+    year: Option<u16>,
+}
+
+fn name_matches(name: &str, pattern: &Option<String>) -> bool {
+    match pattern {
+        Some(pattern) => name.to_lowercase().contains(&pattern.to_lowercase()),
+        None => true,
+    }
+}
+
+impl<'a> WthorQuery<'a> {
+    pub fn player(mut self, name: &str) -> Self {
+        self.player = Some(name.to_string());
+        self
+    }
+
+    pub fn black(mut self, name: &str) -> Self {
+        self.black = Some(name.to_string());
+        self
+    }
+
+    pub fn white(mut self, name: &str) -> Self {
+        self.white = Some(name.to_string());
+        self
+    }
+
+    pub fn tournament(mut self, name: &str) -> Self {
+        self.tournament = Some(name.to_string());
+        self
+    }
+
+    pub fn year(mut self, year: u16) -> Self {
+        self.year = Some(year);
+        self
+    }
+
+    fn matches(&self, game: &WthorGame) -> bool {
+        let database = self.database;
+        let (black, white) = (database.player_name(game.black), database.player_name(game.white));
+        (self.player.is_none() || name_matches(&black, &self.player) || name_matches(&white, &self.player))
+            && name_matches(&black, &self.black)
+            && name_matches(&white, &self.white)
+            && name_matches(&database.tournament_name(game.tournament), &self.tournament)
+            && self.year.is_none_or(|year| year == game.year)
+    }
+
+    pub fn games(self) -> impl Iterator<Item = &'a WthorGame> {
+        self.database.games.iter().filter(move |game| self.matches(game))
+    }
+}
+
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let (mut jou, mut trn, mut wtb) = (None, None, vec![]);
+    let mut filters: Vec<(String, String)> = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--jou" | "--trn" | "--player" | "--black" | "--white" | "--tournament" | "--year" => {
+                let value = iter.next().ok_or(format!("missing value for {}", arg))?.clone();
+                match arg.as_str() {
+                    "--jou" => jou = Some(value),
+                    "--trn" => trn = Some(value),
+                    _ => filters.push((arg.clone(), value)),
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown wthor argument '{}'", arg)),
+            _ => wtb.push(arg.clone()),
+        }
+    }
+    if wtb.is_empty() {
+        return Err("usage: othello wthor [--jou WTHOR.JOU] [--trn WTHOR.TRN] [filters] <file.wtb>...".to_string());
+    }
+    let database = WthorDatabase::load(&wtb, jou.as_deref(), trn.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut query = database.query();
+    for (filter, value) in filters.iter() {
+        query = match filter.as_str() {
+            "--player" => query.player(value),
+            "--black" => query.black(value),
+            "--white" => query.white(value),
+            "--tournament" => query.tournament(value),
+            _ => query.year(value.parse().map_err(|_| format!("--year expects a year, got '{}'", value))?),
+        };
+    }
+
+    let (mut shown, mut invalid) = (0, 0);
+    for wthor_game in query.games() {
+        shown += 1;
+        let header = format!(
+            "{} {}: {} - {} {}-{}",
+            wthor_game.year,
+            database.tournament_name(wthor_game.tournament),
+            database.player_name(wthor_game.black),
+            database.player_name(wthor_game.white),
+            wthor_game.black_score,
+            64 - wthor_game.black_score as usize
+        );
+        match database.replay(wthor_game) {
+            Ok(game) => println!("{}\n  {}", header, crate::notation::transcript(&game)),
+            Err(error) => {
+                invalid += 1;
+                println!("{}\n  invalid: {}", header, error);
+            }
+        }
+    }
+    println!("{} games, {} with illegal moves", shown, invalid);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::transcript;
+
+    fn header(games: u32, names: u16, year: u16) -> Vec<u8> {
+        let mut bytes = vec![20, 24, 1, 1];
+        bytes.extend_from_slice(&games.to_le_bytes());
+        bytes.extend_from_slice(&names.to_le_bytes());
+        bytes.extend_from_slice(&year.to_le_bytes());
+        bytes.extend_from_slice(&[8, 0, 22, 0]);
+        bytes
+    }
+
+    fn game_record(tournament: u16, black: u16, white: u16, score: u8, moves: &str) -> Vec<u8> {
+        let mut bytes = vec![];
+        for id in [tournament, black, white] {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[score, score]);
+        let squares: Vec<Square> = (0..moves.len() / 2).map(|i| moves[2 * i..2 * i + 2].parse().unwrap()).collect();
+        for i in 0..60 {
+            bytes.push(squares.get(i).map_or(0, |sq| (10 * (sq.y + 1) + sq.x + 1) as u8));
+        }
+        bytes
+    }
+
+    fn names(names: &[&str], size: usize) -> Vec<u8> {
+        let mut bytes = header(0, names.len() as u16, 0);
+        for name in names {
+            let mut record = name.as_bytes().to_vec();
+            record.resize(size, 0);
+            bytes.extend(record);
+        }
+        bytes
+    }
+
+    fn database() -> WthorDatabase {
+        let mut wtb = header(2, 0, 2021);
+        wtb.extend(game_record(1, 0, 1, 40, "f5d6c3d3c4"));
+        wtb.extend(game_record(0, 1, 0, 64, "f5d6a1"));
+        WthorDatabase {
+            players: parse_players(&names(&["Alice", "Bob"], PLAYER_SIZE)).unwrap(),
+            tournaments: parse_tournaments(&names(&["Paris Open", "Online"], TOURNAMENT_SIZE)).unwrap(),
+            games: parse_games(&wtb).unwrap(),
+        }
+    }
+
+    #[test]
+    fn reads_games_players_and_tournaments() {
+        let database = database();
+        assert_eq!(database.games.len(), 2);
+        let first = &database.games[0];
+        assert_eq!((first.year, first.black_score), (2021, 40));
+        assert_eq!(database.player_name(first.black), "Alice");
+        assert_eq!(database.tournament_name(first.tournament), "Online");
+        assert_eq!(database.player_name(7), "player #7");
+
+        let game = database.replay(first).unwrap();
+        assert_eq!(transcript(&game), "f5d6c3d3c4");
+        assert_eq!(game.info.white_player.as_deref(), Some("Bob"));
+        assert_eq!(game.info.result.as_deref(), Some("+16.000"));
+    }
+
+    #[test]
+    fn replay_reports_the_illegal_move() {
+        let database = database();
+        assert_eq!(
+            database.games[1].replay().err(),
+            Some(WthorError::IllegalMove { game: 1, move_number: 3, square: Square { x: 0, y: 0 } })
+        );
+    }
+
+    #[test]
+    fn query_filters_games() {
+        let database = database();
+        assert_eq!(database.query().player("bob").games().count(), 2);
+        assert_eq!(database.query().black("alice").games().count(), 1);
+        assert_eq!(database.query().tournament("paris").games().count(), 1);
+        assert_eq!(database.query().year(2020).games().count(), 0);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let mut wtb = header(2, 0, 2021);
+        wtb.extend(game_record(0, 0, 1, 40, "f5"));
+        assert_eq!(
+            parse_games(&wtb).err(),
+            Some(WthorError::Truncated { expected: HEADER_SIZE + 2 * GAME_SIZE, found: HEADER_SIZE + GAME_SIZE })
+        );
+    }
+}